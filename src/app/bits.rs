@@ -0,0 +1,65 @@
+use crate::app::{App, change::Change, state::AppState};
+use crate::ops::Endian;
+
+// the bit editor works on a copy of up to 4 bytes under the cursor, so switching the width or
+// byte order keeps the toggled bits, and only enter writes them back
+
+impl App {
+    pub fn open_bits(&mut self) {
+        let idx = self.get_idx();
+        if idx >= self.data.len() {
+            self.message = Some("no byte under the cursor".into());
+            return;
+        }
+        self.is_selecting = false;
+        self.bit_bytes = self.data[idx..(idx + 4).min(self.data.len())].to_vec();
+        if self.bit_unit.width > self.bit_bytes.len() {
+            self.bit_unit.width = 1;
+        }
+        self.bit_cursor = self.bit_cursor.min(self.bit_unit.bits() - 1);
+        self.state = AppState::Bits;
+    }
+
+    pub fn bit_value(&self) -> u64 {
+        self.bit_unit.read(&self.bit_bytes[..self.bit_unit.width])
+    }
+
+    pub fn toggle_bit(&mut self) {
+        let value = self.bit_value() ^ (1 << self.bit_cursor);
+        let width = self.bit_unit.width;
+        self.bit_bytes[..width].copy_from_slice(&self.bit_unit.to_bytes(value));
+    }
+
+    pub fn move_bit(&mut self, by: i32) {
+        let bit = self.bit_cursor as i32 + by;
+        if (0..self.bit_unit.bits() as i32).contains(&bit) {
+            self.bit_cursor = bit as u32;
+        }
+    }
+
+    // 8, 16 and 32 bits, skipping the ones that would run past the end of the file
+    pub fn cycle_bit_width(&mut self) {
+        self.bit_unit.width = match self.bit_unit.width {
+            1 if self.bit_bytes.len() >= 2 => 2,
+            2 if self.bit_bytes.len() >= 4 => 4,
+            _ => 1,
+        };
+        self.bit_cursor = self.bit_cursor.min(self.bit_unit.bits() - 1);
+    }
+
+    pub fn toggle_bit_endian(&mut self) {
+        self.bit_unit.endian = match self.bit_unit.endian {
+            Endian::Little => Endian::Big,
+            Endian::Big => Endian::Little,
+        };
+    }
+
+    pub fn commit_bits(&mut self) {
+        self.state = AppState::Move;
+        let idx = self.get_idx();
+        let old = self.data[idx..(idx + self.bit_bytes.len())].to_vec();
+        if old != self.bit_bytes {
+            self.do_change(Change::Edit(idx, old, self.bit_bytes.clone()));
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashSet;
+
 use crate::app::App;
 
 #[derive(Debug, Clone)]
@@ -5,36 +7,305 @@ pub enum Change {
     Edit(usize, Vec<u8>, Vec<u8>),
     Insert(usize, Vec<u8>),
     Delete(usize, Vec<u8>),
+    Group(Vec<Change>),
+}
+
+impl Change {
+    pub fn describe(&self) -> String {
+        let bytes = |n: usize| {
+            if n == 1 {
+                "1 byte".into()
+            } else {
+                format!("{n} bytes")
+            }
+        };
+        match self {
+            Change::Edit(idx, _old, new) => format!("edit {} at {idx:08X}", bytes(new.len())),
+            Change::Insert(idx, new) => format!("insert {} at {idx:08X}", bytes(new.len())),
+            Change::Delete(idx, old) => format!("delete {} at {idx:08X}", bytes(old.len())),
+            Change::Group(changes) => match changes.first() {
+                Some(first) => format!("{} (+{} more)", first.describe(), changes.len() - 1),
+                None => "original".into(),
+            },
+        }
+    }
+}
+
+// where the cursor and selection were, so undo/redo can jump back there
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CursorState {
+    pub idx: usize,
+    pub is_selecting: bool,
+    pub is_block: bool,
+    pub selection_start: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct HistoryNode {
+    pub change: Change,
+    pub before: CursorState,
+    pub after: CursorState,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    // the branch redo follows, the one we last came back from
+    pub redo_child: Option<usize>,
+}
+
+// an undo tree, node 0 is the file as it was opened and nothing is ever thrown away
+#[derive(Debug, Clone)]
+pub struct History {
+    pub nodes: Vec<HistoryNode>,
+    pub current: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History {
+            nodes: vec![HistoryNode {
+                change: Change::Group(Vec::new()),
+                before: CursorState::default(),
+                after: CursorState::default(),
+                parent: None,
+                children: Vec::new(),
+                redo_child: None,
+            }],
+            current: 0,
+        }
+    }
+
+    fn push(&mut self, change: Change, cursor: CursorState) {
+        let idx = self.nodes.len();
+        self.nodes.push(HistoryNode {
+            change,
+            before: cursor,
+            after: cursor,
+            parent: Some(self.current),
+            children: Vec::new(),
+            redo_child: None,
+        });
+        self.nodes[self.current].children.push(idx);
+        self.nodes[self.current].redo_child = Some(idx);
+        self.current = idx;
+    }
+
+    fn ancestors(&self, mut idx: usize) -> Vec<usize> {
+        let mut path = vec![idx];
+        while let Some(parent) = self.nodes[idx].parent {
+            path.push(parent);
+            idx = parent;
+        }
+        path
+    }
+
+    // the nodes to undo (from current upwards) and to redo (downwards) to reach target
+    pub fn path_to(&self, target: usize) -> (Vec<usize>, Vec<usize>) {
+        let from = self.ancestors(self.current);
+        let mut to = self.ancestors(target);
+        let to_set: HashSet<usize> = to.iter().copied().collect();
+        let common = *from.iter().find(|idx| to_set.contains(idx)).unwrap_or(&0);
+
+        let undo = from.into_iter().take_while(|idx| *idx != common).collect();
+        to.truncate(to.iter().position(|idx| *idx == common).unwrap_or(to.len()));
+        to.reverse();
+        (undo, to)
+    }
+
+    // every node with its branch depth, depth first, for showing the tree
+    pub fn tree_order(&self) -> Vec<(usize, usize)> {
+        let mut order = Vec::new();
+        let mut stack = vec![(0, 0)];
+        while let Some((idx, depth)) = stack.pop() {
+            order.push((idx, depth));
+            let children = &self.nodes[idx].children;
+            for (i, child) in children.iter().enumerate().rev() {
+                stack.push((*child, depth + (i > 0) as usize));
+            }
+        }
+        order
+    }
 }
 
 impl App {
     pub fn do_change(&mut self, change: Change) {
-        self.changes.push(change.clone());
+        self.apply_change(&change);
+        self.changed = true;
+
+        if self.grouping {
+            // the group only becomes a node once something actually changes
+            if let Some(before) = self.group_before.take() {
+                self.history.push(Change::Group(Vec::new()), before);
+            }
+            if let Change::Group(changes) = &mut self.history.nodes[self.history.current].change {
+                changes.push(change);
+                return;
+            }
+        }
+
+        self.history.push(change, self.cursor_state());
+    }
+
+    // everything done until end_group() gets undone at once
+    pub fn begin_group(&mut self) {
+        if self.grouping {
+            return;
+        }
+        self.grouping = true;
+        self.group_before = Some(self.cursor_state());
+    }
+
+    pub fn end_group(&mut self) {
+        if !self.grouping {
+            return;
+        }
+        self.grouping = false;
+        // nothing changed, so there's no node to close
+        if self.group_before.take().is_some() {
+            return;
+        }
+
+        let current = self.history.current;
+        if let Change::Group(changes) = &mut self.history.nodes[current].change
+            && changes.len() == 1
+        {
+            self.history.nodes[current].change = changes.remove(0);
+        }
+    }
+
+    fn apply_change(&mut self, change: &Change) {
         match change {
-            Change::Edit(idx, _old, new) => self.replace_data(idx, new),
-            Change::Insert(idx, new) => self.insert_data(idx, new),
-            Change::Delete(idx, old) => self.delete_data(idx, old.len()),
+            Change::Edit(idx, _old, new) => self.replace_data(*idx, new.clone()),
+            Change::Insert(idx, new) => self.insert_data(*idx, new.clone()),
+            Change::Delete(idx, old) => self.delete_data(*idx, old.len()),
+            Change::Group(changes) => {
+                for change in changes {
+                    self.apply_change(change);
+                }
+            }
         }
     }
 
-    pub fn undo_change(&mut self, change: Change) {
-        self.made_changes.push(change.clone());
+    fn revert_change(&mut self, change: &Change) {
         match change {
-            Change::Edit(idx, old, _new) => self.replace_data(idx, old),
-            Change::Insert(idx, new) => self.delete_data(idx, new.len()),
-            Change::Delete(idx, old) => self.insert_data(idx, old),
+            Change::Edit(idx, old, new) => {
+                // an edit past the end grew the file, so shrink it back
+                if new.len() > old.len() {
+                    self.delete_data(idx + old.len(), new.len() - old.len());
+                }
+                self.replace_data(*idx, old.clone());
+            }
+            Change::Insert(idx, new) => self.delete_data(*idx, new.len()),
+            Change::Delete(idx, old) => self.insert_data(*idx, old.clone()),
+            Change::Group(changes) => {
+                for change in changes.iter().rev() {
+                    self.revert_change(change);
+                }
+            }
         }
     }
 
+    fn undo_node(&mut self, idx: usize) {
+        let node = self.history.nodes[idx].clone();
+        self.revert_change(&node.change);
+        self.set_cursor_state(node.before);
+        if let Some(parent) = node.parent {
+            self.history.nodes[parent].redo_child = Some(idx);
+            self.history.current = parent;
+        }
+    }
+
+    fn redo_node(&mut self, idx: usize) {
+        let node = self.history.nodes[idx].clone();
+        self.apply_change(&node.change);
+        self.set_cursor_state(node.after);
+        if let Some(parent) = node.parent {
+            self.history.nodes[parent].redo_child = Some(idx);
+        }
+        self.history.current = idx;
+    }
+
     pub fn undo(&mut self) {
-        if let Some(change) = self.changes.pop() {
-            self.undo_change(change);
+        self.end_group();
+        if self.history.current != 0 {
+            self.undo_node(self.history.current);
         }
     }
 
     pub fn redo(&mut self) {
-        if let Some(change) = self.made_changes.pop() {
-            self.do_change(change);
+        self.end_group();
+        let current = &self.history.nodes[self.history.current];
+        if let Some(child) = current.redo_child.or(current.children.last().copied()) {
+            self.redo_node(child);
+        }
+    }
+
+    // jumps to any state in the tree, undoing and redoing along the way
+    pub fn travel_to(&mut self, target: usize) {
+        self.end_group();
+        let (undo, redo) = self.history.path_to(target);
+        for idx in undo {
+            self.undo_node(idx);
         }
+        for idx in redo {
+            self.redo_node(idx);
+        }
+    }
+
+    // called once the key that made the change is fully handled
+    pub fn record_cursor(&mut self) {
+        if !self.changed {
+            return;
+        }
+        self.changed = false;
+        let cursor = self.cursor_state();
+        let current = self.history.current;
+        self.history.nodes[current].after = cursor;
+    }
+
+    pub fn cursor_state(&self) -> CursorState {
+        CursorState {
+            idx: self.get_idx(),
+            is_selecting: self.is_selecting,
+            is_block: self.is_block,
+            selection_start: self.selection_start,
+        }
+    }
+
+    pub fn set_cursor_state(&mut self, cursor: CursorState) {
+        self.set_idx(cursor.idx);
+        self.is_selecting = cursor.is_selecting;
+        self.is_block = cursor.is_block;
+        self.selection_start = cursor.selection_start;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0 - 1 - 2
+    //      \- 3 - 4
+    fn two_branches() -> History {
+        let mut history = History::new();
+        for i in 0..4 {
+            if i == 2 {
+                history.current = 1;
+            }
+            history.push(Change::Insert(i, vec![i as u8]), CursorState::default());
+        }
+        history
+    }
+
+    #[test]
+    fn path_across_branches() {
+        let mut history = two_branches();
+        assert_eq!(history.nodes[1].children, vec![2, 3]);
+        assert_eq!(history.path_to(2), (vec![4, 3], vec![2]));
+        assert_eq!(history.path_to(0), (vec![4, 3, 1], vec![]));
+        assert_eq!(history.path_to(4), (vec![], vec![]));
+
+        history.current = 2;
+        assert_eq!(history.path_to(4), (vec![2], vec![3, 4]));
+        history.current = 0;
+        assert_eq!(history.path_to(4), (vec![], vec![1, 3, 4]));
     }
 }
@@ -0,0 +1,113 @@
+use std::io::{Write, stdout};
+
+use crate::app::{App, change::Change, command::MAX_SIZE, state::AppState};
+use crate::config::PasteMode;
+use crate::encode::{CopyFormat, base64, decode_hex};
+
+impl App {
+    pub fn copy(&mut self) {
+        self.yank(self.get_selection_data());
+        self.is_selecting = false;
+        copy_to_system(&self.clipboard);
+    }
+
+    // every yank lands in the clipboard, the history and the register picked with "
+    fn yank(&mut self, data: Vec<u8>) {
+        if let Some(register) = self.register.take() {
+            self.registers.insert(register, data.clone());
+        }
+        self.yank_history.retain(|yanked| *yanked != data);
+        self.yank_history.push_front(data.clone());
+        self.yank_history.truncate(self.config.editor.yank_history);
+        self.clipboard = data;
+    }
+
+    // what p pastes: the picked register if there is one, the clipboard otherwise
+    pub fn paste_register(&mut self, mode: PasteMode, count: usize) {
+        let data = match self.register.take() {
+            Some(register) => match self.registers.get(&register) {
+                Some(data) => data.clone(),
+                None => {
+                    self.message = Some(format!("register '{register}' is empty"));
+                    return;
+                }
+            },
+            None => self.clipboard.clone(),
+        };
+        if data.len().saturating_mul(count) > MAX_SIZE {
+            self.message = Some("that would paste more than 1 GiB".into());
+            return;
+        }
+        self.paste(data.repeat(count), mode);
+    }
+
+    // the yank history (newest first) followed by the named registers, for the picker
+    pub fn register_list(&self) -> Vec<(char, &[u8])> {
+        let mut names: Vec<&char> = self.registers.keys().collect();
+        names.sort();
+        let history = self.yank_history.iter().zip('0'..='9');
+        history
+            .map(|(data, name)| (name, data.as_slice()))
+            .chain(
+                names
+                    .into_iter()
+                    .map(|name| (*name, self.registers[name].as_slice())),
+            )
+            .collect()
+    }
+
+    pub fn paste_from_list(&mut self, idx: usize) {
+        self.state = AppState::Move;
+        if let Some((_, data)) = self.register_list().get(idx) {
+            let data = data.to_vec();
+            self.paste(data, self.config.editor.paste_mode);
+        }
+    }
+
+    pub fn copy_as(&mut self, format: CopyFormat) {
+        let text = format.encode(&self.get_selection_data());
+        self.is_selecting = false;
+        copy_to_system(text.as_bytes());
+    }
+
+    pub fn other_paste_mode(&self) -> PasteMode {
+        match self.config.editor.paste_mode {
+            PasteMode::Insert => PasteMode::Overwrite,
+            PasteMode::Overwrite => PasteMode::Insert,
+        }
+    }
+
+    pub fn paste(&mut self, data: Vec<u8>, mode: PasteMode) {
+        if data.is_empty() {
+            return;
+        }
+        let idx = self.get_idx();
+        let len = data.len();
+        match mode {
+            PasteMode::Insert => self.do_change(Change::Insert(idx, data)),
+            PasteMode::Overwrite => {
+                // only the bytes that exist get captured, the rest extends the file
+                let old =
+                    self.data[idx.min(self.data.len())..(idx + len).min(self.data.len())].to_vec();
+                self.do_change(Change::Edit(idx, old, data));
+            }
+        }
+        self.selection_start = self.get_idx();
+        self.is_selecting = true;
+        self.is_block = false;
+        self.set_idx(self.selection_start + len - 1);
+    }
+
+    // text coming from the terminal (bracketed paste), hex if it looks like hex
+    pub fn paste_text(&mut self, text: String) {
+        let data = decode_hex(&text).unwrap_or_else(|| text.into_bytes());
+        self.paste(data, self.config.editor.paste_mode);
+    }
+}
+
+// osc 52, so it also works over ssh
+pub fn copy_to_system(data: &[u8]) {
+    let mut out = stdout();
+    let _ = write!(out, "\x1b]52;c;{}\x07", base64(data));
+    let _ = out.flush();
+}
@@ -0,0 +1,466 @@
+use std::{fs, path::PathBuf};
+
+use crate::app::{App, change::Change, events::MAX_COUNT};
+use crate::config::{MAX_BYTES_PER_ROW, ModifiedMode, PasteMode, RowWidth};
+use crate::encode::{TextEncoding, decode_hex, encode_text};
+use crate::ops::{self, Endian, Transform, Unit};
+
+impl App {
+    pub fn run_command(&mut self) {
+        let command = std::mem::take(&mut self.command);
+        // everything after the name, untouched, for shell commands
+        let rest = command
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest.trim());
+        let mut words = command.split_whitespace();
+        let name = words.next();
+        let args: Vec<&str> = words.collect();
+
+        let result = match name {
+            Some("fill") => self.fill(args),
+            Some(
+                op @ ("xor" | "and" | "or" | "not" | "add" | "sub" | "rol" | "ror" | "shl" | "shr"
+                | "swapnibbles" | "revbits"),
+            ) => self.transform(op, args),
+            Some(op @ ("swap" | "reverse" | "reverserecords" | "sortrecords")) => {
+                self.reorder(op, args)
+            }
+            Some(name @ ("insertfile" | "overwritefile")) => self.read_file(name, args),
+            Some("truncate") => self.truncate(),
+            Some("extend") => self.extend(args),
+            Some("insertbytes") => self.insert_bytes(args),
+            Some("modified") => match args.first().and_then(|s| ModifiedMode::parse(s)) {
+                Some(mode) => {
+                    self.modified_mode = mode;
+                    Ok(())
+                }
+                None => Err("usage: modified off|load|save".into()),
+            },
+            Some("select") => match args.first() {
+                Some(len) => parse_number(len).and_then(|len| self.select_length(len as usize)),
+                None => Err("usage: select <length>".into()),
+            },
+            Some("selectto") => match args.first() {
+                Some(offset) => parse_number(offset).and_then(|o| self.select_to(o as usize)),
+                None => Err("usage: selectto <offset>".into()),
+            },
+            Some("selectall") => {
+                self.select_all();
+                Ok(())
+            }
+            Some("insert") => self.write_value(rest, PasteMode::Insert),
+            Some("overwrite") => self.write_value(rest, PasteMode::Overwrite),
+            Some("edit") => self.edit_command(args),
+            Some(name @ ("pipe" | "pipeinsert" | "pipeshow")) => self.pipe(name, rest),
+            Some("width") => match args.first().and_then(|s| RowWidth::parse(s)) {
+                Some(width) => {
+                    self.row_width = width;
+                    if let RowWidth::Fixed(n) = width {
+                        self.set_bytes_per_row(n);
+                    }
+                    Ok(())
+                }
+                None => Err(format!("usage: width <1-{MAX_BYTES_PER_ROW}>|auto")),
+            },
+            Some("group") => match args.first().map(|s| parse_number(s)) {
+                Some(Ok(n)) if n > 0 => {
+                    self.group = n as usize;
+                    Ok(())
+                }
+                _ => Err("usage: group <bytes per group>".into()),
+            },
+            Some("replay") => self.replay_command(args),
+            Some("savemacro") => match args.as_slice() {
+                [register] if register.len() == 1 => {
+                    self.save_macro(register.chars().next().unwrap())
+                }
+                _ => Err("usage: savemacro <register>".into()),
+            },
+            Some(name) => Err(format!("unknown command '{name}'")),
+            None => Ok(()),
+        };
+
+        if let Err(e) = result {
+            self.message = Some(e);
+        }
+    }
+
+    // replaces the selected bytes with new ones of the same length, as one change
+    pub fn replace_selection(&mut self, new: Vec<u8>) {
+        let (x, y) = self.selection_range();
+        let old = self.data[x..(y + 1)].to_vec();
+        let mut span = old.clone();
+        let mut new = new.into_iter();
+        for (start, end) in self.selection_ranges() {
+            for b in &mut span[(start - x)..(end + 1 - x)] {
+                *b = new.next().unwrap_or(*b);
+            }
+        }
+        self.do_change(Change::Edit(x, old, span));
+    }
+
+    fn fill(&mut self, mut args: Vec<&str>) -> Result<(), String> {
+        let len = self.get_selection_data().len();
+        if len == 0 {
+            return Err("nothing to fill".into());
+        }
+
+        let new = match args.first().copied() {
+            Some("inc") | Some("dec") => {
+                let unit = take_unit(&mut args);
+                let start = args.get(1).map(|s| parse_number(s)).unwrap_or(Ok(0))?;
+                let step = args.get(2).map(|s| parse_number(s)).unwrap_or(Ok(1))? as i64;
+                let step = if args[0] == "dec" { -step } else { step };
+                ops::fill_sequence(len, start, step, unit)
+            }
+            Some("random") => {
+                let seed = match args.get(1) {
+                    Some(s) => parse_number(s)?,
+                    None => ops::random_seed(),
+                };
+                self.message = Some(format!("random seed: {seed}"));
+                ops::fill_random(len, seed)
+            }
+            Some(_) => {
+                let pattern = decode_hex(&args.join(" ")).ok_or("invalid fill pattern")?;
+                ops::fill_pattern(len, &pattern)
+            }
+            None => return Err("usage: fill <hex>|inc|dec|random ...".into()),
+        };
+
+        self.replace_selection(new);
+        Ok(())
+    }
+
+    fn transform(&mut self, op: &str, mut args: Vec<&str>) -> Result<(), String> {
+        let data = self.get_selection_data();
+        if data.is_empty() {
+            return Err("nothing to transform".into());
+        }
+
+        if args.first() == Some(&"key") {
+            let key = decode_hex(&args[1..].join(" ")).ok_or("invalid key")?;
+            let op: fn(u64) -> Transform = match op {
+                "xor" => Transform::Xor,
+                "and" => Transform::And,
+                "or" => Transform::Or,
+                _ => return Err(format!("{op} doesn't take a key")),
+            };
+            self.replace_selection(ops::transform_key(&data, op, &key));
+            return Ok(());
+        }
+
+        let unit = take_unit(&mut args);
+        let arg = || -> Result<u64, String> {
+            let value = parse_number(args.first().ok_or(format!("{op} needs a value"))?)?;
+            if value > unit.mask() {
+                return Err(format!("{value:#X} doesn't fit in {} bits", unit.bits()));
+            }
+            Ok(value)
+        };
+        let amount = || -> Result<u32, String> {
+            match arg()? {
+                n if n > unit.bits() as u64 => Err(format!(
+                    "can't {op} by {n}, the unit is {} bits",
+                    unit.bits()
+                )),
+                n => Ok(n as u32),
+            }
+        };
+
+        let op = match op {
+            "xor" => Transform::Xor(arg()?),
+            "and" => Transform::And(arg()?),
+            "or" => Transform::Or(arg()?),
+            "not" => Transform::Not,
+            "add" => Transform::Add(arg()?),
+            "sub" => Transform::Sub(arg()?),
+            "rol" => Transform::Rol(amount()?),
+            "ror" => Transform::Ror(amount()?),
+            "shl" => Transform::Shl(amount()?),
+            "shr" => Transform::Shr(amount()?),
+            "swapnibbles" => Transform::SwapNibbles,
+            _ => Transform::ReverseBits,
+        };
+
+        if data.len() < unit.width {
+            return Err(format!("selection is smaller than {} bytes", unit.width));
+        }
+        self.replace_selection(ops::transform(&data, op, unit));
+        Ok(())
+    }
+
+    fn reorder(&mut self, op: &str, args: Vec<&str>) -> Result<(), String> {
+        let data = self.get_selection_data();
+        if data.is_empty() {
+            return Err("nothing to reorder".into());
+        }
+
+        let size = || -> Result<usize, String> {
+            let size = parse_number(args.first().ok_or(format!("{op} needs a size"))?)? as usize;
+            if size == 0 || size > data.len() {
+                return Err(format!("invalid size {size}"));
+            }
+            Ok(size)
+        };
+
+        let new = match op {
+            "swap" => match size()? {
+                size @ (2 | 4 | 8) => ops::swap_bytes(&data, size),
+                _ => return Err("swap size must be 2, 4 or 8".into()),
+            },
+            "reverse" => data.iter().rev().copied().collect(),
+            "reverserecords" => ops::reverse_records(&data, size()?),
+            _ => ops::sort_records(&data, size()?),
+        };
+
+        self.replace_selection(new);
+        Ok(())
+    }
+
+    fn read_file(&mut self, name: &str, args: Vec<&str>) -> Result<(), String> {
+        let path = expand_path(
+            args.first()
+                .ok_or(format!("usage: {name} <path> [offset] [length]"))?,
+        );
+        let mode = match name {
+            "insertfile" => PasteMode::Insert,
+            _ => PasteMode::Overwrite,
+        };
+        let mut data = fs::read(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+
+        let offset = args.get(1).map(|s| parse_number(s)).unwrap_or(Ok(0))? as usize;
+        if offset > data.len() {
+            return Err(format!("offset is past the end of {}", path.display()));
+        }
+        data.drain(..offset);
+        if let Some(length) = args.get(2) {
+            data.truncate(parse_number(length)? as usize);
+        }
+
+        if data.is_empty() {
+            return Err("nothing to insert".into());
+        }
+        self.paste(data, mode);
+        Ok(())
+    }
+
+    fn truncate(&mut self) -> Result<(), String> {
+        let idx = self.get_idx();
+        if idx >= self.data.len() {
+            return Err("nothing to truncate".into());
+        }
+        self.is_selecting = false;
+        self.do_change(Change::Delete(idx, self.data[idx..].to_vec()));
+        Ok(())
+    }
+
+    fn extend(&mut self, args: Vec<&str>) -> Result<(), String> {
+        let size = parse_size(args.first().ok_or("usage: extend <size> [fill]")?)?;
+        let fill = parse_byte(args.get(1).copied().unwrap_or("00"))?;
+        if size <= self.data.len() {
+            return Err(format!("file is already {} bytes", self.data.len()));
+        }
+        let len = self.data.len();
+        self.do_change(Change::Insert(len, vec![fill; size - len]));
+        Ok(())
+    }
+
+    fn insert_bytes(&mut self, args: Vec<&str>) -> Result<(), String> {
+        let n = parse_size(args.first().ok_or("usage: insertbytes <n> [value]")?)?;
+        let value = parse_byte(args.get(1).copied().unwrap_or("00"))?;
+        if n == 0 {
+            return Err("nothing to insert".into());
+        }
+        self.paste(vec![value; n], PasteMode::Insert);
+        Ok(())
+    }
+
+    // "u32be 1234", "f64 0.5" or "utf16 hello\n"
+    fn write_value(&mut self, args: &str, mode: PasteMode) -> Result<(), String> {
+        let (kind, value) = args
+            .split_once(char::is_whitespace)
+            .ok_or("usage: insert|overwrite <type> <value>")?;
+        // extra spaces after the type aren't part of the value
+        let value = value.trim_start();
+        let data = match TextEncoding::parse(kind) {
+            Some(encoding) => {
+                // quotes keep spaces at the ends
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                encode_text(value, encoding)?
+            }
+            None => encode_number(kind, value.trim())?,
+        };
+        if data.is_empty() {
+            return Err("nothing to write".into());
+        }
+        self.is_selecting = false;
+        self.paste(data, mode);
+        Ok(())
+    }
+
+    fn replay_command(&mut self, args: Vec<&str>) -> Result<(), String> {
+        let register = match args.first() {
+            Some(register) if register.len() == 1 => register.chars().next().unwrap(),
+            _ => return Err("usage: replay <register> [count]".into()),
+        };
+        let count = args.get(1).map(|s| parse_number(s)).unwrap_or(Ok(1))?;
+        self.replay(register, count.min(MAX_COUNT as u64) as usize);
+        Ok(())
+    }
+}
+
+// a single hex byte, like the ones in the editor
+pub fn parse_byte(s: &str) -> Result<u8, String> {
+    match decode_hex(s).as_deref() {
+        Some([b]) => Ok(*b),
+        _ => Err(format!("invalid byte '{s}'")),
+    }
+}
+
+pub fn expand_path(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+// anything bigger than this is most likely a typo, and would only run out of memory
+pub const MAX_SIZE: usize = 1 << 30;
+
+// a number of bytes to create
+fn parse_size(s: &str) -> Result<usize, String> {
+    match parse_number(s)? {
+        size if size > MAX_SIZE as u64 => Err(format!("{s} bytes is too big, the limit is 1 GiB")),
+        size => Ok(size as usize),
+    }
+}
+
+// decimal, or hex with 0x
+pub fn parse_number(s: &str) -> Result<u64, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number '{s}'"))
+}
+
+// u8 to u64, i8 to i64, f32 or f64, optionally followed by le or be
+pub fn encode_number(kind: &str, value: &str) -> Result<Vec<u8>, String> {
+    let (name, endian) = match kind.strip_suffix("be") {
+        Some(name) => (name, Endian::Big),
+        None => (kind.strip_suffix("le").unwrap_or(kind), Endian::Little),
+    };
+    let width = match name.get(1..) {
+        Some("8") => 1,
+        Some("16") => 2,
+        Some("32") => 4,
+        Some("64") => 8,
+        _ => 0,
+    };
+    let unit = Unit { width, endian };
+
+    let bits = match (name.chars().next(), width) {
+        (_, 0) | (Some('f'), 1 | 2) => return Err(format!("unknown type '{kind}'")),
+        (Some('u'), _) => {
+            let n = parse_number(value)?;
+            if n > unit.mask() {
+                return Err(format!("{value} doesn't fit in {name}"));
+            }
+            n
+        }
+        (Some('i'), _) => {
+            let (negative, digits) = match value.strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, value),
+            };
+            let n = parse_number(digits)? as i128;
+            let n = if negative { -n } else { n };
+            let max = (unit.mask() >> 1) as i128;
+            if n > max || n < -max - 1 {
+                return Err(format!("{value} doesn't fit in {name}"));
+            }
+            n as u64 & unit.mask()
+        }
+        (Some('f'), 4) => {
+            let f: f32 = value
+                .parse()
+                .map_err(|_| format!("invalid float '{value}'"))?;
+            f.to_bits() as u64
+        }
+        (Some('f'), _) => {
+            let f: f64 = value
+                .parse()
+                .map_err(|_| format!("invalid float '{value}'"))?;
+            f.to_bits()
+        }
+        _ => return Err(format!("unknown type '{kind}'")),
+    };
+    Ok(unit.to_bytes(bits))
+}
+
+// removes things like "u16", "le" or "u32be" from the args, defaults to u8 little endian
+pub fn take_unit(args: &mut Vec<&str>) -> Unit {
+    let mut unit = Unit {
+        width: 1,
+        endian: Endian::Little,
+    };
+
+    args.retain(|arg| {
+        let (width, endian) = match arg.strip_suffix("le").or(arg.strip_suffix("be")) {
+            Some(width) => arg.split_at(width.len()),
+            None => (*arg, ""),
+        };
+        let width = match width {
+            "" => None,
+            "u8" => Some(1),
+            "u16" => Some(2),
+            "u32" => Some(4),
+            "u64" => Some(8),
+            _ => return true,
+        };
+        let endian = match endian {
+            "" => None,
+            "le" => Some(Endian::Little),
+            "be" => Some(Endian::Big),
+            _ => return true,
+        };
+
+        if let Some(width) = width {
+            unit.width = width;
+        }
+        if let Some(endian) = endian {
+            unit.endian = endian;
+        }
+        false
+    });
+
+    unit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units() {
+        let mut args = vec!["inc", "u8"];
+        let unit = take_unit(&mut args);
+        assert_eq!(args, ["inc"]);
+        assert_eq!(unit.width, 1);
+
+        let mut args = vec!["inc", "0", "1", "u32be"];
+        let unit = take_unit(&mut args);
+        assert_eq!(args, ["inc", "0", "1"]);
+        assert_eq!((unit.width, unit.endian == Endian::Big), (4, true));
+
+        let mut args = vec!["be", "u16", "ble"];
+        let unit = take_unit(&mut args);
+        assert_eq!(args, ["ble"]);
+        assert_eq!((unit.width, unit.endian == Endian::Big), (2, true));
+    }
+}
@@ -1,19 +1,116 @@
 use crate::app::{App, change::Change, state::AppState};
+use crate::encode::CopyFormat;
+use crate::keys::normalize;
 use color_eyre::eyre::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+// counts past this are typos
+pub const MAX_COUNT: usize = 1_000_000;
+
 impl App {
     pub fn handle_crossterm_events(&mut self) -> Result<()> {
         match event::read()? {
-            Event::Key(key) if key.kind == KeyEventKind::Press => self.on_key_event(key),
+            // the kitty protocol reports shift and friends on their own, they mean nothing here
+            Event::Key(KeyEvent {
+                code: KeyCode::Modifier(_),
+                ..
+            }) => return Ok(()),
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                let key = match self.kitty_keyboard {
+                    true => normalize(key, matches!(self.state, AppState::Move | AppState::Edit)),
+                    false => key,
+                };
+                if self.recording.is_some() {
+                    self.recorded_keys.push(key);
+                }
+                self.on_key_event(key);
+            }
+            Event::Paste(text) if self.state == AppState::Move => {
+                self.end_group();
+                self.paste_text(text);
+            }
+            Event::Paste(text) if self.state == AppState::Command => {
+                self.command
+                    .extend(text.chars().filter(|c| *c != '\n' && *c != '\r'));
+            }
             _ => {}
         }
+        self.record_cursor();
         Ok(())
     }
 
-    fn on_key_event(&mut self, key: KeyEvent) {
+    pub fn on_key_event(&mut self, key: KeyEvent) {
+        self.message = None;
+
+        // consecutive typed bytes get undone together
+        let typing = matches!(self.state, AppState::Move | AppState::Edit)
+            && matches!(key.code, KeyCode::Char(c) if c.is_ascii_hexdigit() || c == 'i');
+        // a replayed macro is one group, it gets closed once the replay is done
+        if !typing && self.replay_depth == 0 {
+            self.end_group();
+        }
+
+        if let Some(prefix) = self.pending.take() {
+            let count = self.count.take();
+            if let KeyCode::Char(c) = key.code {
+                self.on_register(prefix, c, count);
+            }
+            return;
+        }
+
+        // a count typed after + repeats the next key
+        if self.state == AppState::Move
+            && let Some(count) = self.count
+        {
+            if let KeyCode::Char(c) = key.code
+                && let Some(digit) = c.to_digit(10)
+            {
+                self.count = Some((count * 10 + digit as usize).min(MAX_COUNT));
+                return;
+            }
+            self.count = None;
+            let count = count.max(1);
+            match key.code {
+                KeyCode::Esc => {}
+                // pasting again would land inside the last paste, so paste it all at once
+                KeyCode::Char('p') => self.paste_register(self.config.editor.paste_mode, count),
+                KeyCode::Char('P') => self.paste_register(self.other_paste_mode(), count),
+                // the count waits for the register, it's meant for what comes after
+                KeyCode::Char('"' | '@') => {
+                    self.handle_key(key);
+                    self.count = Some(count);
+                }
+                _ => {
+                    self.begin_group();
+                    let mut changed = std::mem::take(&mut self.changed);
+                    for _ in 0..count {
+                        let before = (self.get_idx(), self.history.current);
+                        self.handle_key(key);
+                        // a key that neither moves nor changes anything won't start doing so
+                        let stuck =
+                            !self.changed && before == (self.get_idx(), self.history.current);
+                        changed |= std::mem::take(&mut self.changed);
+                        if stuck || self.state != AppState::Move || !self.running {
+                            break;
+                        }
+                    }
+                    self.changed = changed;
+                    if self.replay_depth == 0 {
+                        self.end_group();
+                    }
+                }
+            }
+            return;
+        }
+
+        self.handle_key(key);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
         match self.state {
             AppState::Move => match (key.modifiers, key.code) {
                 (_, KeyCode::Char('q')) => self.quit(),
+                (_, KeyCode::Right) if self.nibble_mode => self.move_nibble_right(),
+                (_, KeyCode::Left) if self.nibble_mode => self.move_nibble_left(),
                 (_, KeyCode::Right) => self.move_right(),
                 (_, KeyCode::Left) => self.move_left(),
                 (_, KeyCode::Up) => self.move_up(),
@@ -21,64 +118,64 @@ impl App {
                 (_, KeyCode::PageUp) => self.move_page_up(),
                 (_, KeyCode::PageDown) => self.move_page_down(),
 
-                (_, KeyCode::Char('v')) => {
-                    if self.is_selecting {
-                        self.is_selecting = false;
-                    } else {
-                        self.is_selecting = true;
-                        self.selection_start = self.get_idx();
-                    }
-                }
+                (KeyModifiers::CONTROL, KeyCode::Char('a')) => self.select_all(),
+                (_, KeyCode::Char('v')) => self.toggle_selection(false),
+                (_, KeyCode::Char('V')) => self.toggle_selection(true),
 
                 (_, KeyCode::Esc) => {
                     self.is_selecting = false;
                 }
 
-                (_, KeyCode::Char('y')) => {
-                    self.clipboard = self.get_selection_data();
-                    self.is_selecting = false;
+                (_, KeyCode::Char('y')) => self.copy(),
+                (_, KeyCode::Char('Y')) => {
+                    self.menu_idx = 0;
+                    self.state = AppState::CopyAs;
                 }
-                (_, KeyCode::Char('p')) => {
-                    self.do_change(Change::Insert(self.get_idx(), self.clipboard.clone()));
-                    self.selection_start = self.get_idx();
-                    self.is_selecting = true;
-                    self.set_idx(self.selection_start + self.clipboard.len() - 1);
+                (KeyModifiers::CONTROL, KeyCode::Char('p')) => {
+                    self.register = None;
+                    if self.register_list().is_empty() {
+                        self.message = Some("nothing yanked yet".into());
+                    } else {
+                        self.menu_idx = 0;
+                        self.state = AppState::Registers;
+                    }
                 }
+                (_, KeyCode::Char('p')) => self.paste_register(self.config.editor.paste_mode, 1),
+                (_, KeyCode::Char('P')) => self.paste_register(self.other_paste_mode(), 1),
+                (_, KeyCode::Char('"')) => self.pending = Some('"'),
 
                 (_, KeyCode::Backspace) => {
-                    let idx = self.get_idx();
-                    let (x, y) = self.selection_range();
-
                     //since cursor can also be outside data check this lol;
-                    if x == y && y == self.data.len() {
+                    if !self.is_selecting && self.get_idx() == self.data.len() {
                         self.move_left();
-                        return ();
+                        return;
                     }
-
-                    let old = self.data[x..(y + 1)].to_vec();
-
-                    self.do_change(Change::Delete(idx, old));
-
-                    //if where the cursor was now theres nothing then move it!
-                    let new_idx = idx.min(self.data.len().saturating_sub(1));
-                    self.set_idx(new_idx);
+                    self.delete_selection();
                 }
+                (_, KeyCode::Char('x')) => self.cut(),
+                (_, KeyCode::Char('m')) => self.start_drag(),
                 (KeyModifiers::NONE, KeyCode::Char(c)) if c.is_ascii_hexdigit() => {
                     self.is_selecting = false;
-                    self.state = AppState::Edit;
-                    self.is_inserting = false;
-                    self.insert_to_buffer(c);
+                    if self.nibble_mode {
+                        self.begin_group();
+                        self.write_nibble(c);
+                    } else {
+                        self.state = AppState::Edit;
+                        self.is_inserting = false;
+                        self.insert_to_buffer(c);
+                    }
                 }
-
-                /*
-                //i really wanna do this but for some reason it doesnt work with numbers
-                // for later!
+                // digits only come in with shift when the kitty keyboard protocol is on
                 (KeyModifiers::SHIFT, KeyCode::Char(c)) if c.is_ascii_hexdigit() => {
+                    self.is_selecting = false;
                     self.state = AppState::Edit;
                     self.is_inserting = true;
                     self.insert_to_buffer(c);
-                },
-                */
+                }
+                (_, KeyCode::Char('n')) => {
+                    self.nibble_mode = !self.nibble_mode;
+                    self.low_nibble = false;
+                }
                 (_, KeyCode::Char('i')) => {
                     self.is_selecting = false;
                     self.state = AppState::Edit;
@@ -96,6 +193,38 @@ impl App {
                     self.redo();
                 }
                 (_, KeyCode::Char('s')) | (_, KeyCode::Char('S')) => self.save(),
+                (_, KeyCode::Char(']')) => self.next_modified(),
+                (_, KeyCode::Char('[')) => self.previous_modified(),
+                (_, KeyCode::Char('R')) => self.revert_modified(),
+                (_, KeyCode::Char('t')) => {
+                    self.is_selecting = false;
+                    self.menu_idx = self
+                        .history
+                        .tree_order()
+                        .iter()
+                        .position(|(idx, _)| *idx == self.history.current)
+                        .unwrap_or(0);
+                    self.state = AppState::History;
+                }
+                (_, KeyCode::Char('~')) => self.open_bits(),
+                // not # since shift+3 inserts a 3 with the kitty keyboard protocol
+                (_, KeyCode::Char('+')) => self.count = Some(0),
+                (_, KeyCode::Char('r')) => match self.recording {
+                    Some(_) => self.stop_recording(),
+                    None => self.pending = Some('r'),
+                },
+                (_, KeyCode::Char('@')) => self.pending = Some('@'),
+                (_, KeyCode::Char(':')) => {
+                    self.state = AppState::Command;
+                }
+                (_, KeyCode::Char('I')) => {
+                    self.command = "insert ".into();
+                    self.state = AppState::Command;
+                }
+                (_, KeyCode::Char('!')) => {
+                    self.command = "pipe ".into();
+                    self.state = AppState::Command;
+                }
                 (_, KeyCode::Char('h')) | (_, KeyCode::Char('H')) => {
                     self.is_selecting = false;
                     self.state = AppState::Help;
@@ -115,15 +244,12 @@ impl App {
                         let idx = self.get_idx();
                         let new = self.buffer_to_u8();
 
+                        self.begin_group();
                         if self.is_inserting {
                             self.do_change(Change::Insert(idx, vec![new]));
                         } else {
-                            if idx >= self.data.len() {
-                                self.data.push(new);
-                            } else {
-                                let old = self.data[idx];
-                                self.do_change(Change::Edit(idx, vec![old], vec![new]))
-                            }
+                            let old = self.data.get(idx).copied().into_iter().collect();
+                            self.do_change(Change::Edit(idx, old, vec![new]))
                         }
                         self.buffer = [' ', ' '];
                         self.move_right();
@@ -132,9 +258,142 @@ impl App {
                 }
                 _ => {}
             },
+            AppState::CopyAs => match key.code {
+                KeyCode::Up => self.menu_idx = self.menu_idx.saturating_sub(1),
+                KeyCode::Down => self.menu_idx = (self.menu_idx + 1).min(CopyFormat::ALL.len() - 1),
+                KeyCode::Enter => {
+                    self.copy_as(CopyFormat::ALL[self.menu_idx]);
+                    self.state = AppState::Move;
+                }
+                KeyCode::Char(c) if ('1'..='8').contains(&c) => {
+                    self.copy_as(CopyFormat::ALL[c as usize - '1' as usize]);
+                    self.state = AppState::Move;
+                }
+                _ => self.state = AppState::Move,
+            },
+            AppState::Command => match key.code {
+                KeyCode::Esc => {
+                    self.command.clear();
+                    self.state = AppState::Move;
+                }
+                KeyCode::Backspace if self.command.pop().is_none() => {
+                    self.state = AppState::Move;
+                }
+                KeyCode::Enter => {
+                    self.state = AppState::Move;
+                    self.run_command();
+                }
+                KeyCode::Char(c) => self.command.push(c),
+                _ => {}
+            },
+            AppState::Drag => match key.code {
+                KeyCode::Right => self.drag_by(1),
+                KeyCode::Left => self.drag_by(-1),
+                KeyCode::Up => self.drag_by(-(self.bytes_per_row as isize)),
+                KeyCode::Down => self.drag_by(self.bytes_per_row as isize),
+                KeyCode::Enter | KeyCode::Char('m') => self.end_drag(true),
+                KeyCode::Esc => self.end_drag(false),
+                _ => {}
+            },
+            AppState::History => match key.code {
+                KeyCode::Up => self.menu_idx = self.menu_idx.saturating_sub(1),
+                KeyCode::Down => {
+                    self.menu_idx = (self.menu_idx + 1).min(self.history.nodes.len() - 1)
+                }
+                KeyCode::Enter => {
+                    let (idx, _) = self.history.tree_order()[self.menu_idx];
+                    self.travel_to(idx);
+                    self.state = AppState::Move;
+                }
+                _ => self.state = AppState::Move,
+            },
+            AppState::Registers => match key.code {
+                KeyCode::Up => self.menu_idx = self.menu_idx.saturating_sub(1),
+                KeyCode::Down => {
+                    self.menu_idx = (self.menu_idx + 1).min(self.register_list().len() - 1)
+                }
+                KeyCode::Enter => self.paste_from_list(self.menu_idx),
+                KeyCode::Char(c) => {
+                    match self.register_list().iter().position(|(name, _)| *name == c) {
+                        Some(idx) => self.paste_from_list(idx),
+                        None => self.state = AppState::Move,
+                    }
+                }
+                _ => self.state = AppState::Move,
+            },
+            AppState::Output => match key.code {
+                KeyCode::Up => self.menu_idx = self.menu_idx.saturating_sub(1),
+                KeyCode::Down => {
+                    let lines = self.output.lines().count();
+                    let max = lines.saturating_sub(self.frame_height.saturating_sub(2));
+                    self.menu_idx = (self.menu_idx + 1).min(max)
+                }
+                _ => self.state = AppState::Move,
+            },
+            AppState::Bits => match key.code {
+                KeyCode::Left => self.move_bit(1),
+                KeyCode::Right => self.move_bit(-1),
+                KeyCode::Up => self.move_bit(8),
+                KeyCode::Down => self.move_bit(-8),
+                KeyCode::Char(' ') => self.toggle_bit(),
+                KeyCode::Char('w') => self.cycle_bit_width(),
+                KeyCode::Char('e') => self.toggle_bit_endian(),
+                KeyCode::Enter => self.commit_bits(),
+                KeyCode::Esc | KeyCode::Char('q') => self.state = AppState::Move,
+                _ => {}
+            },
             AppState::Help => {
                 self.state = AppState::Move;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::app::App;
+    use crate::keys::{normalize, parse_keys};
+
+    fn press(app: &mut App, keys: &str) {
+        for key in parse_keys(keys).unwrap() {
+            app.on_key_event(key);
+            app.record_cursor();
+        }
+    }
+
+    #[test]
+    fn count_before_macro() {
+        let mut app = App::with_data(&[0; 8]);
+        app.macros.insert('a', parse_keys("<right>").unwrap());
+        press(&mut app, "+5@a");
+        assert_eq!(app.get_idx(), 5);
+    }
+
+    #[test]
+    fn count_before_register() {
+        let mut app = App::with_data(&[]);
+        app.registers.insert('x', vec![0xab]);
+        press(&mut app, "+3\"xp");
+        assert_eq!(app.data, vec![0xab; 3]);
+
+        let mut app = App::with_data(&[]);
+        app.registers.insert('x', vec![0xab]);
+        press(&mut app, "\"x+3p");
+        assert_eq!(app.data, vec![0xab; 3]);
+    }
+
+    #[test]
+    fn count_with_kitty_keys() {
+        let mut app = App::with_data(&[0; 8]);
+        let shifted = |c| normalize(KeyEvent::new(KeyCode::Char(c), KeyModifiers::SHIFT), true);
+        // shift+= is the count, shift+3 still inserts
+        app.on_key_event(shifted('='));
+        press(&mut app, "5<right>");
+        assert_eq!(app.get_idx(), 5);
+        app.on_key_event(shifted('3'));
+        press(&mut app, "3");
+        assert_eq!(app.data[5..7], [0x33, 0]);
+    }
+}
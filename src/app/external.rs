@@ -0,0 +1,274 @@
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process::{self, Command, Stdio},
+    thread,
+};
+
+use color_eyre::Result;
+use ratatui::DefaultTerminal;
+
+use crate::app::{App, change::Change, state::AppState};
+use crate::byte::Byte;
+use crate::encode::decode_hex;
+use crate::ops;
+use crate::terminal;
+
+// :edit writes the selection to a temp file as hex, hands the terminal over to $EDITOR and
+// reads the file back in once it exits. :pipe feeds the selection to a shell command instead
+
+const EDIT_HEADER: &str = "# edit the bytes below, lines starting with # are ignored
+# offsets before a ':' and text after a '|' are ignored too
+";
+
+impl App {
+    pub fn edit_command(&mut self, args: Vec<&str>) -> Result<(), String> {
+        match args.as_slice() {
+            [] => self.edit_annotated = false,
+            ["annotated"] => self.edit_annotated = true,
+            _ => return Err("usage: edit [annotated]".into()),
+        }
+        self.open_editor = true;
+        Ok(())
+    }
+
+    // called from the main loop, since the terminal has to be given up while the editor runs
+    pub fn run_editor(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        self.open_editor = false;
+        terminal::restore(self.kitty_keyboard)?;
+        let result = self.edit_in_editor();
+        *terminal = terminal::init(self.kitty_keyboard)?;
+
+        if let Err(e) = result {
+            self.message = Some(e);
+        }
+        self.record_cursor();
+        Ok(())
+    }
+
+    fn edit_in_editor(&mut self) -> Result<(), String> {
+        let path = create_temp_file(self.format_selection().as_bytes())?;
+
+        let editor = env::var("VISUAL")
+            .or_else(|_| env::var("EDITOR"))
+            .unwrap_or_else(|_| "vi".into());
+        // through the shell, so things like EDITOR="code --wait" work
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{editor} \"$1\""))
+            .arg("hexhog")
+            .arg(&path)
+            .status();
+        let text = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => return Err(format!("{editor} exited with {status}, nothing changed")),
+            Err(e) => return Err(format!("couldn't run {editor}: {e}")),
+        }
+        let new = parse_edited(&text.map_err(|e| e.to_string())?)?;
+        self.replace_resized(new)
+    }
+
+    pub fn pipe(&mut self, name: &str, command: &str) -> Result<(), String> {
+        if command.is_empty() {
+            return Err(format!("usage: {name} <shell command>"));
+        }
+        let output = run_piped(command, self.get_selection_data())?;
+
+        match name {
+            "pipe" => self.replace_resized(output)?,
+            "pipeinsert" => {
+                if output.is_empty() {
+                    return Err(format!("{command} printed nothing"));
+                }
+                // goes right after the selection, so the input stays
+                let (_, y) = self.selection_range();
+                self.is_selecting = false;
+                self.set_idx((y + 1).min(self.data.len()));
+                self.do_change(Change::Insert(self.get_idx(), output.clone()));
+                self.selection_start = self.get_idx();
+                self.is_selecting = true;
+                self.is_block = false;
+                self.set_idx(self.selection_start + output.len() - 1);
+            }
+            _ => {
+                if output.is_empty() {
+                    return Err(format!("{command} printed nothing"));
+                }
+                self.output = match String::from_utf8(output) {
+                    Ok(text) => text.replace('\t', "    "),
+                    Err(e) => hex_dump(e.as_bytes()),
+                };
+                self.menu_idx = 0;
+                self.state = AppState::Output;
+            }
+        }
+        Ok(())
+    }
+
+    fn format_selection(&self) -> String {
+        let mut text = EDIT_HEADER.to_string();
+        for (start, end) in self.selection_ranges() {
+            let Some(data) = self.data.get(start..(end + 1)) else {
+                continue;
+            };
+            for (i, row) in data.chunks(16).enumerate() {
+                let hex: Vec<String> = row.iter().map(|b| format!("{b:02X}")).collect();
+                let hex = hex.join(" ");
+                match self.edit_annotated {
+                    true => {
+                        let ascii: String = row
+                            .iter()
+                            .map(|b| Byte::new(*b).get_char(&self.config))
+                            .collect();
+                        text.push_str(&format!("{:08X}: {hex:<47} |{ascii}|\n", start + i * 16));
+                    }
+                    false => text.push_str(&format!("{hex}\n")),
+                }
+            }
+        }
+        text
+    }
+
+    // same length goes through replace_selection (so block selections work), anything else
+    // replaces the selected bytes as one change, keeping the new bytes selected
+    fn replace_resized(&mut self, new: Vec<u8>) -> Result<(), String> {
+        let old = self.get_selection_data();
+        if new == old {
+            return Ok(());
+        }
+        if new.len() == old.len() && !old.is_empty() {
+            self.replace_selection(new);
+            return Ok(());
+        }
+        if self.is_selecting && self.is_block {
+            return Err(format!(
+                "a block selection has to stay {} bytes, got {}",
+                old.len(),
+                new.len()
+            ));
+        }
+
+        let (x, _) = self.selection_range();
+        let x = x.min(self.data.len());
+        let mut changes = Vec::new();
+        if !old.is_empty() {
+            changes.push(Change::Delete(x, old));
+        }
+        if !new.is_empty() {
+            changes.push(Change::Insert(x, new.clone()));
+        }
+        self.do_change(Change::Group(changes));
+
+        match new.len() {
+            0 => {
+                self.is_selecting = false;
+                self.set_idx(x.min(self.data.len().saturating_sub(1)));
+            }
+            len => {
+                self.is_selecting = true;
+                self.selection_start = x;
+                self.set_idx(x + len - 1);
+            }
+        }
+        Ok(())
+    }
+}
+
+// a fresh file only we can read, with a random name, so nobody else on a shared /tmp can
+// point it somewhere else with a symlink or swap what gets read back in
+fn create_temp_file(contents: &[u8]) -> Result<PathBuf, String> {
+    let mut last_error = None;
+    for _ in 0..16 {
+        let suffix: String = ops::fill_random(8, ops::random_seed() ^ process::id() as u64)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect();
+        let path = env::temp_dir().join(format!("hexhog-{suffix}.hex"));
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents)
+                    .map_err(|e| format!("{}: {e}", path.display()))?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => last_error = Some(e),
+            Err(e) => return Err(format!("{}: {e}", path.display())),
+        }
+    }
+    Err(format!(
+        "couldn't create a temp file: {}",
+        last_error.unwrap()
+    ))
+}
+
+fn parse_edited(text: &str) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // annotated lines look like "00000010: DE AD BE EF |....|"
+        let line = line.split_once('|').map_or(line, |(hex, _)| hex);
+        let line = line.split_once(':').map_or(line, |(_, hex)| hex);
+        if line.trim().is_empty() {
+            continue;
+        }
+        match decode_hex(line) {
+            Some(bytes) => data.extend(bytes),
+            None => return Err(format!("line {}: invalid hex, nothing changed", i + 1)),
+        }
+    }
+    Ok(data)
+}
+
+fn run_piped(command: &str, input: Vec<u8>) -> Result<Vec<u8>, String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("couldn't run {command}: {e}"))?;
+
+    // written from another thread, so a command that prints before reading can't block us
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(&input);
+    });
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(match stderr.lines().next() {
+            Some(line) => format!("{command}: {line}"),
+            None => format!("{command} exited with {}", output.status),
+        });
+    }
+    Ok(output.stdout)
+}
+
+fn hex_dump(data: &[u8]) -> String {
+    let rows: Vec<String> = data
+        .chunks(16)
+        .enumerate()
+        .map(|(i, row)| {
+            let hex: Vec<String> = row.iter().map(|b| format!("{b:02X}")).collect();
+            format!("{:08X}  {}", i * 16, hex.join(" "))
+        })
+        .collect();
+    rows.join("\n")
+}
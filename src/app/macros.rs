@@ -0,0 +1,156 @@
+use std::fs;
+
+use crossterm::event::KeyEvent;
+
+use crate::app::{App, events::MAX_COUNT};
+use crate::config::Config;
+use crate::keys::format_keys;
+
+impl App {
+    pub fn on_register(&mut self, prefix: char, register: char, count: Option<usize>) {
+        if !register.is_ascii_lowercase() {
+            self.message = Some(format!("invalid register '{register}'"));
+            return;
+        }
+        match prefix {
+            '"' => {
+                self.register = Some(register);
+                // "x with a count in front still counts the paste after it
+                self.count = count;
+            }
+            'r' => {
+                self.recording = Some(register);
+                self.recorded_keys.clear();
+            }
+            _ => self.replay(register, count.unwrap_or(1)),
+        }
+    }
+
+    pub fn stop_recording(&mut self) {
+        let Some(register) = self.recording.take() else {
+            return;
+        };
+        // the last key is the r that stopped it
+        self.recorded_keys.pop();
+        self.macros
+            .insert(register, std::mem::take(&mut self.recorded_keys));
+    }
+
+    pub fn replay(&mut self, register: char, count: usize) {
+        let Some(keys) = self.macros.get(&register).cloned() else {
+            self.message = Some(format!("register '{register}' is empty"));
+            return;
+        };
+        // a macro replaying itself would never end, so the whole replay stops
+        if self.replay_depth >= 16 {
+            self.message = Some("macros nested too deep".into());
+            self.stop_replay = true;
+            return;
+        }
+
+        // the whole replay gets undone at once, like a counted key
+        if self.replay_depth == 0 {
+            self.stop_replay = false;
+            self.begin_group();
+        }
+        self.replay_depth += 1;
+        let mut changed_any = false;
+        for _ in 0..count.min(MAX_COUNT) {
+            let before = (self.get_idx(), self.history.current);
+            let mut changed = false;
+            for key in &keys {
+                self.on_key_event(*key);
+                changed |= self.changed;
+                self.record_cursor();
+                if self.stop_replay || !self.running {
+                    break;
+                }
+            }
+            changed_any |= changed;
+            // a macro that neither moves nor changes anything won't start doing so
+            let stuck = !changed && before == (self.get_idx(), self.history.current);
+            if stuck || self.stop_replay || !self.running {
+                break;
+            }
+        }
+        self.replay_depth -= 1;
+        // so a replay around this one sees it did something
+        self.changed |= changed_any;
+        if self.replay_depth == 0 {
+            self.end_group();
+        }
+    }
+
+    // writes the macro into the [macros] table, leaving the rest of the config as it was
+    pub fn save_macro(&mut self, register: char) -> Result<(), String> {
+        let keys: &[KeyEvent] = self
+            .macros
+            .get(&register)
+            .ok_or(format!("register '{register}' is empty"))?;
+        let line = format!("{register} = {}", toml::Value::String(format_keys(keys)));
+
+        let path = Config::path().ok_or("couldn't find the config directory")?;
+        let text = fs::read_to_string(&path).unwrap_or_default();
+        let mut lines: Vec<String> = text.lines().map(String::from).collect();
+
+        match lines.iter().position(|l| l.trim() == "[macros]") {
+            Some(header) => {
+                let end = lines[header + 1..]
+                    .iter()
+                    .position(|l| l.trim_start().starts_with('['))
+                    .map_or(lines.len(), |i| header + 1 + i);
+                let existing = lines[header + 1..end].iter().position(|l| {
+                    l.split('=').next().map(str::trim) == Some(&register.to_string())
+                });
+                match existing {
+                    Some(i) => lines[header + 1 + i] = line,
+                    None => lines.insert(header + 1, line),
+                }
+            }
+            None => {
+                if lines.last().is_some_and(|l| !l.is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.push("[macros]".into());
+                lines.push(line);
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        fs::write(&path, lines.join("\n") + "\n")
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        self.message = Some(format!("saved macro '{register}' to {}", path.display()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::App;
+    use crate::keys::parse_keys;
+
+    #[test]
+    fn replay_is_one_undo_step() {
+        let mut app = App::with_data(&[0; 4]);
+        app.macros.insert('a', parse_keys("ff<esc>").unwrap());
+        app.replay('a', 3);
+        assert_eq!(app.data, [0xff, 0xff, 0xff, 0]);
+        app.undo();
+        assert_eq!(app.data, [0; 4]);
+    }
+
+    #[test]
+    fn replay_stops_when_stuck() {
+        let mut app = App::with_data(&[0; 4]);
+        app.macros.insert('a', parse_keys("<right>").unwrap());
+        app.replay('a', usize::MAX);
+        assert_eq!(app.get_idx(), 4);
+
+        // each level replays the one below three times, nesting as deep as it can
+        app.macros.insert('b', parse_keys("@b@b@b").unwrap());
+        app.replay('b', 1);
+        assert_eq!(app.message.as_deref(), Some("macros nested too deep"));
+    }
+}
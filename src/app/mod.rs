@@ -1,6 +1,14 @@
+mod bits;
 mod change;
+mod clipboard;
+mod command;
 mod events;
+mod external;
+mod macros;
+mod modified;
 mod render;
+mod selection;
 mod state;
+mod undofile;
 mod utils;
 pub use state::{App, Args};
@@ -0,0 +1,101 @@
+use crate::app::{App, change::Change};
+use crate::config::ModifiedMode;
+
+impl App {
+    fn original(&self) -> &[u8] {
+        match self.modified_mode {
+            ModifiedMode::Load => &self.loaded_data,
+            _ => &self.saved_data,
+        }
+    }
+
+    pub fn is_modified(&self, pos: usize) -> bool {
+        let original = self.original();
+        pos < self.data.len() && original.get(pos) != Some(&self.data[pos])
+    }
+
+    // the run of modified bytes around pos
+    fn modified_region(&self, pos: usize) -> Option<(usize, usize)> {
+        if !self.is_modified(pos) {
+            return None;
+        }
+        let mut start = pos;
+        while start > 0 && self.is_modified(start - 1) {
+            start -= 1;
+        }
+        let mut end = pos;
+        while self.is_modified(end + 1) {
+            end += 1;
+        }
+        Some((start, end))
+    }
+
+    pub fn next_modified(&mut self) {
+        let mut pos = self.get_idx();
+        while self.is_modified(pos) {
+            pos += 1;
+        }
+        match (pos..self.data.len()).find(|p| self.is_modified(*p)) {
+            Some(pos) => self.set_idx(pos),
+            None => self.message = Some("no more modified bytes".into()),
+        }
+    }
+
+    pub fn previous_modified(&mut self) {
+        let start = match self.modified_region(self.get_idx()) {
+            Some((start, _)) => start,
+            None => self.get_idx(),
+        };
+        match (0..start).rev().find(|p| self.is_modified(*p)) {
+            Some(pos) => self.set_idx(self.modified_region(pos).map_or(pos, |(s, _)| s)),
+            None => self.message = Some("no more modified bytes".into()),
+        }
+    }
+
+    pub fn revert_modified(&mut self) {
+        let Some((start, end)) = self.modified_region(self.get_idx()) else {
+            self.message = Some("nothing modified here".into());
+            return;
+        };
+        let original_len = self.original().len();
+
+        self.is_selecting = false;
+        if start >= original_len {
+            // all of it was added past the original end
+            self.do_change(Change::Delete(start, self.data[start..=end].to_vec()));
+            self.set_idx(start.min(self.data.len()));
+        } else {
+            let inside = end.min(original_len - 1);
+            let old = self.data[start..=inside].to_vec();
+            let new = self.original()[start..=inside].to_vec();
+            let mut changes = vec![Change::Edit(start, old, new)];
+            // the part that ran past the original end goes away with it
+            if end >= original_len {
+                changes.push(Change::Delete(
+                    original_len,
+                    self.data[original_len..=end].to_vec(),
+                ));
+            }
+            self.do_change(Change::Group(changes));
+            self.set_idx(start);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app::App;
+
+    #[test]
+    fn revert_past_the_end() {
+        let mut app = App::with_data(&[1, 2, 3]);
+        app.data = vec![1, 2, 9, 9, 9];
+        app.set_idx(3);
+        app.revert_modified();
+        assert_eq!(app.data, [1, 2, 3]);
+        app.undo();
+        assert_eq!(app.data, [1, 2, 9, 9, 9]);
+        app.redo();
+        assert_eq!(app.data, [1, 2, 3]);
+    }
+}
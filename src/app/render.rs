@@ -9,6 +9,9 @@ use ratatui::{
 };
 
 use crate::byte::Byte;
+use crate::config::{MAX_BYTES_PER_ROW, ModifiedMode, RowWidth};
+use crate::encode::CopyFormat;
+use crate::ops::Endian;
 
 impl App {
     pub fn render(&mut self, frame: &mut Frame) {
@@ -24,6 +27,12 @@ impl App {
             .split(frame.area());
 
         self.frame_height = layout[1].height as usize;
+        if self.row_width == RowWidth::Auto {
+            let fitting = self.fitting_bytes_per_row(layout[1].width);
+            if fitting != self.bytes_per_row {
+                self.set_bytes_per_row(fitting);
+            }
+        }
 
         self.render_title(frame, layout[0]);
         self.render_status(frame, layout[2]);
@@ -32,6 +41,47 @@ impl App {
         if self.state == AppState::Help {
             self.render_help_popup(frame, layout[1]);
         }
+        if self.state == AppState::CopyAs {
+            self.render_copy_popup(frame, layout[1]);
+        }
+        if self.state == AppState::History {
+            self.render_history_popup(frame, layout[1]);
+        }
+        if self.state == AppState::Bits {
+            self.render_bits_popup(frame, layout[1]);
+        }
+        if self.state == AppState::Registers {
+            self.render_registers_popup(frame, layout[1]);
+        }
+        if self.state == AppState::Output {
+            self.render_output_popup(frame, layout[1]);
+        }
+    }
+
+    // the hex column, two digits per byte with separators between bytes and between groups
+    fn hex_width(&self, bytes_per_row: usize) -> usize {
+        let groups = (bytes_per_row - 1) / self.group;
+        let bytes = bytes_per_row - 1 - groups;
+        bytes_per_row * 2
+            + bytes * self.config.editor.byte_separator.chars().count()
+            + groups * self.config.editor.group_separator.chars().count()
+    }
+
+    // address, hex column with its borders and padding, and the text column
+    fn row_width(&self, bytes_per_row: usize) -> usize {
+        8 + self.hex_width(bytes_per_row) + 2 + 2 + bytes_per_row
+    }
+
+    // as many bytes as fit, rounded down to whole groups once there's room for one
+    fn fitting_bytes_per_row(&self, width: u16) -> usize {
+        let fitting = (1..=(width as usize).min(MAX_BYTES_PER_ROW))
+            .take_while(|n| self.row_width(*n) <= width as usize)
+            .last()
+            .unwrap_or(1);
+        match fitting < self.group {
+            true => fitting,
+            false => fitting - fitting % self.group,
+        }
     }
 
     fn render_background(&self, frame: &mut Frame) {
@@ -52,19 +102,53 @@ impl App {
     }
 
     fn render_status(&self, frame: &mut Frame, area: Rect) {
+        let (status_text, alignment) = if self.state == AppState::Command {
+            (format!(":{}█", self.command), Alignment::Left)
+        } else if self.state == AppState::Drag {
+            (
+                " moving selection │ enter - done │ esc - cancel ".to_string(),
+                Alignment::Center,
+            )
+        } else if let Some(message) = &self.message {
+            (format!(" {message} "), Alignment::Center)
+        } else {
+            let recording = match self.recording {
+                Some(register) => format!(" recording @{register} │"),
+                None => String::new(),
+            };
+            let register = match self.register {
+                Some(register) => format!(" \"{register} │"),
+                None => String::new(),
+            };
+            let count = match self.count {
+                Some(0) => " count: _ │".to_string(),
+                Some(count) => format!(" count: {count} │"),
+                None => String::new(),
+            };
+            let nibble = match self.nibble_mode {
+                true => " nibble │",
+                false => "",
+            };
+            (
+                format!(
+                    "{recording}{register}{count}{nibble} h - help │ cursor: {:08X} │ size: {} bytes ",
+                    self.get_idx(),
+                    self.data.len(),
+                ),
+                Alignment::Center,
+            )
+        };
+        // at least as wide as the rows, wider if the text needs it
+        let width = self
+            .row_width(self.bytes_per_row)
+            .max(status_text.chars().count());
         let used_area = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints([Constraint::Length(8 + 48 + 2 + 2 + 16)])
+            .constraints([Constraint::Length(saturate(width))])
             .flex(Flex::Center)
             .split(area);
-
-        let status_text = format!(
-            " h - help │ cursor: {:08X} │ size: {} bytes ",
-            self.get_idx(),
-            self.data.len(),
-        );
         let status = Paragraph::new(status_text)
-            .alignment(Alignment::Center)
+            .alignment(alignment)
             .fg(self.config.colorscheme.accent)
             .reversed();
         frame.render_widget(status, used_area[0]);
@@ -75,8 +159,8 @@ impl App {
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Length(8),
-                Constraint::Length(48 + 2 + 2),
-                Constraint::Length(16),
+                Constraint::Length(saturate(self.hex_width(self.bytes_per_row) + 2 + 2)),
+                Constraint::Length(saturate(self.bytes_per_row)),
             ])
             .flex(Flex::Center)
             .split(area);
@@ -88,7 +172,7 @@ impl App {
         let mut offset = 0;
 
         for i in self.starting_line..self.starting_line + area.height as usize {
-            let row_start = i * 16;
+            let row_start = i * self.bytes_per_row;
 
             if row_start > self.data.len() {
                 break;
@@ -107,7 +191,7 @@ impl App {
             let mut hex_line = Vec::new();
             let mut ascii_line = Vec::new();
 
-            for j in 0..16 {
+            for j in 0..self.bytes_per_row {
                 let pos = row_start + j - offset;
                 if pos > self.data.len() {
                     break;
@@ -125,7 +209,7 @@ impl App {
                         .fg(self.config.colorscheme.primary)
                         .reversed()
                 } else if pos < self.data.len() {
-                    let byte = Byte::new(self.data[pos as usize]);
+                    let byte = Byte::new(self.data[pos]);
                     let mut style = byte.get_style(&self.config);
                     style = if cursor_here {
                         match self.is_selecting {
@@ -133,23 +217,34 @@ impl App {
                             true => style.fg(self.config.colorscheme.primary).reversed(),
                         }
                     } else {
-                        match self.is_selecting {
+                        match self.is_selected(pos) {
+                            true => style
+                                .bg(self.config.colorscheme.select)
+                                .fg(self.config.colorscheme.primary),
                             false => style,
-                            true => {
-                                let (x, y) = self.selection_range();
-                                if x <= pos && pos <= y {
-                                    style
-                                        .bg(self.config.colorscheme.select)
-                                        .fg(self.config.colorscheme.primary)
-                                } else {
-                                    style
-                                }
-                            }
                         }
                     };
+                    if !cursor_here
+                        && self.modified_mode != ModifiedMode::Off
+                        && self.is_modified(pos)
+                    {
+                        style = style.fg(self.config.colorscheme.modified);
+                    }
                     ascii_line
                         .push(Span::from(byte.get_char(&self.config).to_string()).set_style(style));
-                    byte.get_hex().set_style(style)
+                    if cursor_here && self.nibble_mode {
+                        // only the half being edited gets the cursor
+                        let normal = byte.get_style(&self.config);
+                        let (high, low) = match self.low_nibble {
+                            true => (normal, style),
+                            false => (style, normal),
+                        };
+                        let hex = byte.get_hex();
+                        hex_line.push(Span::from(hex[..1].to_string()).set_style(high));
+                        Span::from(hex[1..].to_string()).set_style(low)
+                    } else {
+                        byte.get_hex().set_style(style)
+                    }
                 } else if cursor_here {
                     Span::from("  ")
                         .fg(self.config.colorscheme.primary)
@@ -162,24 +257,17 @@ impl App {
 
                 // spacing
 
-                let spacing = if j == 7 {
-                    "  "
-                } else if j < 15 {
-                    " "
-                } else {
+                let spacing = if j + 1 == self.bytes_per_row {
                     ""
+                } else if j % self.group == self.group - 1 {
+                    self.config.editor.group_separator.as_str()
+                } else {
+                    self.config.editor.byte_separator.as_str()
                 };
 
-                hex_line.push(match self.is_selecting {
-                    true => {
-                        let (x, y) = self.selection_range();
-                        if x <= pos && pos < y {
-                            spacing.bg(self.config.colorscheme.select).into()
-                        } else {
-                            spacing.into()
-                        }
-                    }
-                    _ => spacing.into(),
+                hex_line.push(match self.is_selected(pos) && self.is_selected(pos + 1) {
+                    true => spacing.bg(self.config.colorscheme.select),
+                    false => spacing.into(),
                 })
             }
 
@@ -235,6 +323,61 @@ impl App {
                 Span::styled("pgup,pgdn", Style::default().fg(accent)),
                 Span::styled(" - move screen", Style::default().fg(primary)),
             ]),
+            Line::from(vec![
+                Span::styled("Y", Style::default().fg(accent)),
+                Span::styled(" - copy as   ", Style::default().fg(primary)),
+                Span::styled("P", Style::default().fg(accent)),
+                Span::styled(" - paste other mode", Style::default().fg(primary)),
+            ]),
+            Line::from(vec![
+                Span::styled(":", Style::default().fg(accent)),
+                Span::styled(" - command   ", Style::default().fg(primary)),
+                Span::styled("x", Style::default().fg(accent)),
+                Span::styled(" - cut      ", Style::default().fg(primary)),
+                Span::styled("m", Style::default().fg(accent)),
+                Span::styled(" - move", Style::default().fg(primary)),
+            ]),
+            Line::from(vec![
+                Span::styled("V", Style::default().fg(accent)),
+                Span::styled(" - block sel ", Style::default().fg(primary)),
+                Span::styled("t", Style::default().fg(accent)),
+                Span::styled(" - history  ", Style::default().fg(primary)),
+                Span::styled("R", Style::default().fg(accent)),
+                Span::styled(" - revert", Style::default().fg(primary)),
+            ]),
+            Line::from(vec![
+                Span::styled("r", Style::default().fg(accent)),
+                Span::styled(" - record    ", Style::default().fg(primary)),
+                Span::styled("@", Style::default().fg(accent)),
+                Span::styled(" - replay   ", Style::default().fg(primary)),
+                Span::styled("~", Style::default().fg(accent)),
+                Span::styled(" - bits", Style::default().fg(primary)),
+            ]),
+            Line::from(vec![
+                Span::styled("[,]", Style::default().fg(accent)),
+                Span::styled(" - prev/next modified  ", Style::default().fg(primary)),
+                Span::styled("n", Style::default().fg(accent)),
+                Span::styled(" - nibble", Style::default().fg(primary)),
+            ]),
+            Line::from(vec![
+                Span::styled("+", Style::default().fg(accent)),
+                Span::styled(" - count     ", Style::default().fg(primary)),
+                Span::styled("!", Style::default().fg(accent)),
+                Span::styled(" - pipe", Style::default().fg(primary)),
+            ]),
+            Line::from(vec![
+                Span::styled("\"", Style::default().fg(accent)),
+                Span::styled(" - register  ", Style::default().fg(primary)),
+                Span::styled("ctrl-p", Style::default().fg(accent)),
+                Span::styled(" - paste from list", Style::default().fg(primary)),
+            ]),
+            Line::from(vec![
+                Span::styled("I", Style::default().fg(accent)),
+                Span::styled(
+                    " - insert a value (e.g. u32be 1234)",
+                    Style::default().fg(primary),
+                ),
+            ]),
         ];
 
         let popup = Paragraph::new(Text::from(lines)).block(
@@ -257,7 +400,7 @@ impl App {
         let popup_layout = Layout::default()
             .direction(Direction::Vertical)
             .flex(Flex::End)
-            .constraints(vec![Constraint::Length(6)])
+            .constraints(vec![Constraint::Length(14)])
             .split(popup_layout[0]);
 
         frame.render_widget(Clear, popup_layout[0]);
@@ -267,4 +410,359 @@ impl App {
         );
         frame.render_widget(popup, popup_layout[0]);
     }
+
+    fn render_copy_popup(&self, frame: &mut Frame, area: Rect) {
+        let accent = self.config.colorscheme.accent;
+        let primary = self.config.colorscheme.primary;
+
+        let lines: Vec<Line> = CopyFormat::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, format)| {
+                let line = Line::from(vec![
+                    Span::styled(format!("{}", i + 1), Style::default().fg(accent)),
+                    Span::styled(
+                        format!(" - {:<16}", format.name()),
+                        Style::default().fg(primary),
+                    ),
+                ]);
+                if i == self.menu_idx {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let popup = Paragraph::new(Text::from(lines)).block(
+            Block::bordered()
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .fg(primary)
+                .padding(Padding::symmetric(1, 0))
+                .title_top(Line::from(vec![Span::styled(
+                    " copy as ",
+                    Style::default().fg(accent),
+                )])),
+        );
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(25)])
+            .split(area);
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(CopyFormat::ALL.len() as u16 + 2)])
+            .split(popup_layout[0]);
+
+        frame.render_widget(Clear, popup_layout[0]);
+        frame.buffer_mut().set_style(
+            popup_layout[0],
+            Style::default().bg(self.config.colorscheme.background),
+        );
+        frame.render_widget(popup, popup_layout[0]);
+    }
+
+    fn render_bits_popup(&self, frame: &mut Frame, area: Rect) {
+        let accent = self.config.colorscheme.accent;
+        let primary = self.config.colorscheme.primary;
+
+        let value = self.bit_value();
+        let bits = self.bit_unit.bits();
+        let mut lines = Vec::new();
+
+        // 8 bits per row, most significant first
+        for row in (0..bits / 8).rev() {
+            let positions = (0..8).rev().map(|i| row * 8 + i);
+            lines.push(Line::from(
+                positions
+                    .clone()
+                    .map(|bit| Span::styled(format!("{bit:>3}"), Style::default().fg(accent).dim()))
+                    .collect::<Vec<_>>(),
+            ));
+            lines.push(Line::from(
+                positions
+                    .map(|bit| {
+                        let span = Span::styled(
+                            format!("{:>3}", value >> bit & 1),
+                            Style::default().fg(primary),
+                        );
+                        match bit == self.bit_cursor {
+                            true => span.reversed(),
+                            false => span,
+                        }
+                    })
+                    .collect::<Vec<_>>(),
+            ));
+        }
+
+        let endian = match self.bit_unit.endian {
+            Endian::Little => "little",
+            Endian::Big => "big",
+        };
+        lines.push(Line::default());
+        lines.push(Line::styled(
+            format!(
+                "0x{value:0width$X} = {value}",
+                width = self.bit_unit.width * 2
+            ),
+            Style::default().fg(primary),
+        ));
+        lines.push(Line::styled(
+            match self.bit_unit.width {
+                1 => "8 bit".to_string(),
+                _ => format!("{bits} bit, {endian} endian"),
+            },
+            Style::default().fg(primary),
+        ));
+        lines.push(Line::default());
+        lines.push(Line::from(vec![
+            Span::styled("space", Style::default().fg(accent)),
+            Span::styled(" - toggle  ", Style::default().fg(primary)),
+            Span::styled("w", Style::default().fg(accent)),
+            Span::styled(" - width", Style::default().fg(primary)),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("e", Style::default().fg(accent)),
+            Span::styled(" - endian   ", Style::default().fg(primary)),
+            Span::styled("enter", Style::default().fg(accent)),
+            Span::styled(" - done", Style::default().fg(primary)),
+        ]));
+        let height = lines.len() as u16 + 2;
+
+        let popup = Paragraph::new(Text::from(lines)).block(
+            Block::bordered()
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .fg(primary)
+                .padding(Padding::symmetric(1, 0))
+                .title_top(Line::from(vec![Span::styled(
+                    " bits ",
+                    Style::default().fg(accent),
+                )])),
+        );
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(30)])
+            .split(area);
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(height)])
+            .split(popup_layout[0]);
+
+        frame.render_widget(Clear, popup_layout[0]);
+        frame.buffer_mut().set_style(
+            popup_layout[0],
+            Style::default().bg(self.config.colorscheme.background),
+        );
+        frame.render_widget(popup, popup_layout[0]);
+    }
+
+    fn render_history_popup(&self, frame: &mut Frame, area: Rect) {
+        let accent = self.config.colorscheme.accent;
+        let primary = self.config.colorscheme.primary;
+
+        let order = self.history.tree_order();
+        let path = self.history.path_to(0).0;
+        let height = (area.height as usize)
+            .saturating_sub(2)
+            .min(order.len())
+            .max(1);
+        let scroll = self.menu_idx.saturating_sub(height - 1);
+
+        let lines: Vec<Line> = order
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(height)
+            .map(|(i, (idx, depth))| {
+                let marker = if *idx == self.history.current {
+                    "●"
+                } else if *idx == 0 || path.contains(idx) {
+                    "○"
+                } else {
+                    "·"
+                };
+                let line = Line::from(vec![
+                    Span::styled(
+                        format!("{}{marker} {idx:<4}", "│ ".repeat(*depth)),
+                        Style::default().fg(accent),
+                    ),
+                    Span::styled(
+                        format!("{:<36}", self.history.nodes[*idx].change.describe()),
+                        Style::default().fg(primary),
+                    ),
+                ]);
+                if i == self.menu_idx {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let popup = Paragraph::new(Text::from(lines)).block(
+            Block::bordered()
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .fg(primary)
+                .padding(Padding::symmetric(1, 0))
+                .title_top(Line::from(vec![Span::styled(
+                    " history ",
+                    Style::default().fg(accent),
+                )])),
+        );
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(50)])
+            .split(area);
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(height as u16 + 2)])
+            .split(popup_layout[0]);
+
+        frame.render_widget(Clear, popup_layout[0]);
+        frame.buffer_mut().set_style(
+            popup_layout[0],
+            Style::default().bg(self.config.colorscheme.background),
+        );
+        frame.render_widget(popup, popup_layout[0]);
+    }
+
+    fn render_registers_popup(&self, frame: &mut Frame, area: Rect) {
+        let accent = self.config.colorscheme.accent;
+        let primary = self.config.colorscheme.primary;
+
+        let list = self.register_list();
+        let height = (area.height as usize)
+            .saturating_sub(2)
+            .min(list.len())
+            .max(1);
+        let scroll = self.menu_idx.saturating_sub(height - 1);
+
+        let lines: Vec<Line> = list
+            .iter()
+            .enumerate()
+            .skip(scroll)
+            .take(height)
+            .map(|(i, (name, data))| {
+                let mut preview: Vec<String> =
+                    data.iter().take(8).map(|b| format!("{b:02X}")).collect();
+                if data.len() > 8 {
+                    preview.push("…".into());
+                }
+                let line = Line::from(vec![
+                    Span::styled(name.to_string(), Style::default().fg(accent)),
+                    Span::styled(
+                        format!(" - {:<26}{:>6} B", preview.join(" "), data.len()),
+                        Style::default().fg(primary),
+                    ),
+                ]);
+                if i == self.menu_idx {
+                    line.reversed()
+                } else {
+                    line
+                }
+            })
+            .collect();
+
+        let popup = Paragraph::new(Text::from(lines)).block(
+            Block::bordered()
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .fg(primary)
+                .padding(Padding::symmetric(1, 0))
+                .title_top(Line::from(vec![Span::styled(
+                    " paste from ",
+                    Style::default().fg(accent),
+                )])),
+        );
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(42)])
+            .split(area);
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(height as u16 + 2)])
+            .split(popup_layout[0]);
+
+        frame.render_widget(Clear, popup_layout[0]);
+        frame.buffer_mut().set_style(
+            popup_layout[0],
+            Style::default().bg(self.config.colorscheme.background),
+        );
+        frame.render_widget(popup, popup_layout[0]);
+    }
+
+    fn render_output_popup(&self, frame: &mut Frame, area: Rect) {
+        let accent = self.config.colorscheme.accent;
+        let primary = self.config.colorscheme.primary;
+
+        let output: Vec<&str> = self.output.lines().collect();
+        let height = (area.height as usize)
+            .saturating_sub(2)
+            .min(output.len())
+            .max(1);
+        let width = output
+            .iter()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(20) as u16
+            + 4;
+        let scroll = self.menu_idx.min(output.len().saturating_sub(height));
+
+        let lines: Vec<Line> = output
+            .iter()
+            .skip(scroll)
+            .take(height)
+            .map(|line| Line::styled(line.to_string(), Style::default().fg(primary)))
+            .collect();
+
+        let popup = Paragraph::new(Text::from(lines)).block(
+            Block::bordered()
+                .border_type(ratatui::widgets::BorderType::Rounded)
+                .fg(primary)
+                .padding(Padding::symmetric(1, 0))
+                .title_top(Line::from(vec![Span::styled(
+                    " output ",
+                    Style::default().fg(accent),
+                )])),
+        );
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(width)])
+            .split(area);
+
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .flex(Flex::End)
+            .constraints(vec![Constraint::Length(height as u16 + 2)])
+            .split(popup_layout[0]);
+
+        frame.render_widget(Clear, popup_layout[0]);
+        frame.buffer_mut().set_style(
+            popup_layout[0],
+            Style::default().bg(self.config.colorscheme.background),
+        );
+        frame.render_widget(popup, popup_layout[0]);
+    }
+}
+
+// layout lengths are u16, anything wider just gets cut off
+fn saturate(n: usize) -> u16 {
+    u16::try_from(n).unwrap_or(u16::MAX)
 }
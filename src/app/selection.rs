@@ -0,0 +1,139 @@
+use crate::app::{App, change::Change, state::AppState};
+use crate::ops;
+
+impl App {
+    pub fn toggle_selection(&mut self, block: bool) {
+        if !self.is_selecting {
+            self.is_selecting = true;
+            self.selection_start = self.get_idx();
+        } else if self.is_block == block {
+            self.is_selecting = false;
+        }
+        self.is_block = block;
+    }
+
+    // selects len bytes starting at the cursor
+    pub fn select_length(&mut self, len: usize) -> Result<(), String> {
+        let idx = self.get_idx();
+        let left = self.data.len().saturating_sub(idx);
+        if len == 0 || len > left {
+            return Err(format!("can't select {len} bytes, {left} left"));
+        }
+        self.select_span(idx, idx + len - 1);
+        Ok(())
+    }
+
+    // extends the selection (or starts one at the cursor) up to an absolute offset
+    pub fn select_to(&mut self, offset: usize) -> Result<(), String> {
+        if offset >= self.data.len() {
+            return Err(format!("offset {offset:#X} is past the end"));
+        }
+        let start = match self.is_selecting {
+            true => self.selection_start,
+            false => self.get_idx().min(self.data.len() - 1),
+        };
+        self.select_span(start, offset);
+        Ok(())
+    }
+
+    pub fn select_all(&mut self) {
+        if !self.data.is_empty() {
+            self.select_span(0, self.data.len() - 1);
+        }
+    }
+
+    fn select_span(&mut self, start: usize, end: usize) {
+        self.is_selecting = true;
+        self.is_block = false;
+        self.selection_start = start;
+        self.set_idx(end);
+    }
+
+    pub fn delete_selection(&mut self) {
+        if self.is_selecting && self.is_block {
+            self.message = Some("can't delete a block selection".into());
+            return;
+        }
+        let (x, _) = self.selection_range();
+        let old = self.get_selection_data();
+        if old.is_empty() {
+            return;
+        }
+
+        self.do_change(Change::Delete(x, old));
+        self.is_selecting = false;
+
+        //if where the cursor was now theres nothing then move it!
+        self.set_idx(x.min(self.data.len().saturating_sub(1)));
+    }
+
+    pub fn cut(&mut self) {
+        if self.is_selecting && self.is_block {
+            self.message = Some("can't cut a block selection".into());
+            return;
+        }
+        let is_selecting = self.is_selecting;
+        self.copy();
+        self.is_selecting = is_selecting;
+        self.delete_selection();
+    }
+
+    //moving the selection around
+    pub fn start_drag(&mut self) {
+        if self.is_selecting && self.is_block {
+            self.message = Some("can't move a block selection".into());
+            return;
+        }
+        if self.get_selection_data().is_empty() {
+            return;
+        }
+        if !self.is_selecting {
+            self.is_selecting = true;
+            self.selection_start = self.get_idx();
+        }
+        self.drag_from = self.selection_range().0;
+        self.state = AppState::Drag;
+    }
+
+    pub fn drag_by(&mut self, delta: isize) {
+        let (x, y) = self.selection_range();
+        let len = y - x + 1;
+        let Some(to) = x.checked_add_signed(delta) else {
+            return;
+        };
+        if to + len > self.data.len() {
+            return;
+        }
+
+        self.move_block(x, to, len);
+        self.selection_start = self.selection_start - x + to;
+        self.set_idx(self.get_idx() - x + to);
+    }
+
+    pub fn end_drag(&mut self, keep: bool) {
+        self.state = AppState::Move;
+        let (x, y) = self.selection_range();
+        let len = y - x + 1;
+        let from = self.drag_from;
+
+        // put it back first, so the whole move becomes a single change
+        let lo = from.min(x);
+        let new = self.data[lo..from.max(x) + len].to_vec();
+        self.move_block(x, from, len);
+
+        if keep && from != x {
+            let old = self.data[lo..lo + new.len()].to_vec();
+            self.do_change(Change::Edit(lo, old, new));
+        } else {
+            self.selection_start = self.selection_start - x + from;
+            self.set_idx(self.get_idx() - x + from);
+        }
+    }
+
+    fn move_block(&mut self, from: usize, to: usize, len: usize) {
+        let lo = from.min(to);
+        let span = &self.data[lo..from.max(to) + len];
+        let new = ops::move_block(span, from - lo, to - lo, len);
+        self.replace_data(lo, new);
+    }
+}
@@ -1,9 +1,16 @@
-use super::change::Change;
-use crate::config::Config;
+use super::change::{CursorState, History};
+use crate::config::{Config, ModifiedMode, RowWidth};
+use crate::ops::{Endian, Unit};
 use clap::Parser;
 use color_eyre::Result;
+use crossterm::event::KeyEvent;
 use ratatui::DefaultTerminal;
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::Read,
+    path::Path,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about)]
@@ -16,12 +23,25 @@ pub enum AppState {
     Move,
     Edit,
     Help,
+    CopyAs,
+    Command,
+    Drag,
+    History,
+    Bits,
+    Registers,
+    Output,
 }
 
 pub struct App {
     pub config: Config,
     pub file_name: String,
     pub data: Vec<u8>,
+    pub loaded_data: Vec<u8>,
+    pub saved_data: Vec<u8>,
+    pub modified_mode: ModifiedMode,
+    pub row_width: RowWidth,
+    pub bytes_per_row: usize,
+    pub group: usize,
     pub starting_line: usize,
     pub cursor_x: usize,
     pub cursor_y: usize,
@@ -29,12 +49,39 @@ pub struct App {
     pub running: bool,
     pub state: AppState,
     pub buffer: [char; 2],
-    pub changes: Vec<Change>,
-    pub made_changes: Vec<Change>,
+    pub history: History,
+    pub saved_node: usize,
+    pub grouping: bool,
+    pub group_before: Option<CursorState>,
+    pub changed: bool,
     pub is_inserting: bool,
     pub is_selecting: bool,
+    pub is_block: bool,
     pub selection_start: usize,
     pub clipboard: Vec<u8>,
+    pub registers: HashMap<char, Vec<u8>>,
+    pub yank_history: VecDeque<Vec<u8>>,
+    pub register: Option<char>,
+    pub menu_idx: usize,
+    pub command: String,
+    pub message: Option<String>,
+    pub drag_from: usize,
+    pub pending: Option<char>,
+    pub recording: Option<char>,
+    pub recorded_keys: Vec<KeyEvent>,
+    pub macros: HashMap<char, Vec<KeyEvent>>,
+    pub replay_depth: usize,
+    pub stop_replay: bool,
+    pub kitty_keyboard: bool,
+    pub nibble_mode: bool,
+    pub low_nibble: bool,
+    pub bit_bytes: Vec<u8>,
+    pub bit_unit: Unit,
+    pub bit_cursor: u32,
+    pub count: Option<usize>,
+    pub open_editor: bool,
+    pub edit_annotated: bool,
+    pub output: String,
 }
 
 impl App {
@@ -47,9 +94,18 @@ impl App {
             file.read_to_end(&mut data)?;
         }
 
-        Ok(Self {
+        let mut app = Self {
             file_name: args.file,
             running: true,
+            loaded_data: data.clone(),
+            saved_data: data.clone(),
+            modified_mode: config.editor.highlight_modified,
+            row_width: config.editor.bytes_per_row,
+            bytes_per_row: match config.editor.bytes_per_row {
+                RowWidth::Fixed(n) => n,
+                RowWidth::Auto => 16,
+            },
+            group: config.editor.group,
             data,
             starting_line: 0,
             cursor_x: 0,
@@ -57,20 +113,70 @@ impl App {
             frame_height: 0,
             state: AppState::Move,
             buffer: [' ', ' '],
-            changes: Vec::new(),
-            made_changes: Vec::new(),
-            config,
+            history: History::new(),
+            saved_node: 0,
+            grouping: false,
+            group_before: None,
+            changed: false,
             is_inserting: false,
             is_selecting: false,
+            is_block: false,
             selection_start: 0,
             clipboard: Vec::new(),
-        })
+            registers: HashMap::new(),
+            yank_history: VecDeque::new(),
+            register: None,
+            menu_idx: 0,
+            command: String::new(),
+            message: None,
+            drag_from: 0,
+            pending: None,
+            recording: None,
+            recorded_keys: Vec::new(),
+            macros: config.macros.clone(),
+            replay_depth: 0,
+            stop_replay: false,
+            kitty_keyboard: false,
+            nibble_mode: false,
+            low_nibble: false,
+            bit_bytes: Vec::new(),
+            bit_unit: Unit {
+                width: 1,
+                endian: Endian::Little,
+            },
+            bit_cursor: 0,
+            count: None,
+            open_editor: false,
+            edit_annotated: false,
+            output: String::new(),
+            config,
+        };
+        app.load_history();
+        Ok(app)
+    }
+
+    // an app that opened data, with the default config and nothing on disk
+    #[cfg(test)]
+    pub fn with_data(data: &[u8]) -> Self {
+        let mut config = Config::default();
+        config.editor.persistent_undo = false;
+        let args = Args {
+            file: "/nonexistent/hexhog-test".into(),
+        };
+        let mut app = App::new(args, config).unwrap();
+        app.data = data.to_vec();
+        app.loaded_data = data.to_vec();
+        app.saved_data = data.to_vec();
+        app
     }
     pub fn run(mut self, mut terminal: DefaultTerminal) -> Result<()> {
         self.running = true;
         while self.running {
             terminal.draw(|frame| self.render(frame))?;
             self.handle_crossterm_events()?;
+            if self.open_editor {
+                self.run_editor(&mut terminal)?;
+            }
             //maybe this will become an update() func if i need more stuff
             self.set_startingline();
         }
@@ -0,0 +1,337 @@
+use std::{fs, path::PathBuf};
+
+use crate::app::{
+    App,
+    change::{Change, CursorState, History, HistoryNode},
+};
+use crate::encode::{decode_hex, fnv1a};
+
+// the undo tree is kept in the data directory, next to a hash of the file as it is on disk,
+// so it only gets restored if nobody touched the file in the meantime
+
+impl App {
+    pub fn load_history(&mut self) {
+        if !self.config.editor.persistent_undo {
+            return;
+        }
+        let Some((path, name)) = self.history_path() else {
+            return;
+        };
+        let Ok(text) = fs::read_to_string(path) else {
+            return;
+        };
+        let hash = fnv1a(&self.data);
+        if let Some((history, saved)) = read_history(&text, &name, hash, self.data.len()) {
+            self.history = history;
+            self.saved_node = saved;
+            self.set_cursor_state(self.history.nodes[saved].after);
+        }
+    }
+
+    pub fn save_history(&mut self) {
+        self.end_group();
+        if !self.config.editor.persistent_undo || self.history.nodes.len() == 1 {
+            return;
+        }
+        let Some((path, name)) = self.history_path() else {
+            return;
+        };
+        let Ok(disk) = fs::read(&self.file_name) else {
+            return;
+        };
+
+        let text = write_history(&self.history, self.saved_node, &name, fnv1a(&disk));
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Err(e) = fs::write(&path, text) {
+            self.message = Some(format!("couldn't save undo history: {e}"));
+        }
+    }
+
+    fn history_path(&self) -> Option<(PathBuf, String)> {
+        let name = fs::canonicalize(&self.file_name).ok()?;
+        let name = name.to_string_lossy().into_owned();
+        let path = dirs::data_dir()?
+            .join("hexhog")
+            .join("history")
+            .join(format!("{:016x}", fnv1a(name.as_bytes())));
+        Some((path, name))
+    }
+}
+
+fn write_history(history: &History, saved: usize, name: &str, hash: u64) -> String {
+    let mut text = format!("hexhog history 1\n{name}\n{hash:016x}\n{saved}\n");
+    for node in &history.nodes[1..] {
+        let opt = |o: Option<usize>| o.map_or("-".into(), |i| i.to_string());
+        text.push_str(&format!(
+            "{} {} {} {} ",
+            opt(node.parent),
+            opt(node.redo_child),
+            write_cursor(node.before),
+            write_cursor(node.after),
+        ));
+        write_change(&node.change, &mut text);
+        text.push('\n');
+    }
+    text
+}
+
+fn read_history(text: &str, name: &str, hash: u64, len: usize) -> Option<(History, usize)> {
+    let mut lines = text.lines();
+    if lines.next()? != "hexhog history 1"
+        || lines.next()? != name
+        || u64::from_str_radix(lines.next()?, 16).ok()? != hash
+    {
+        return None;
+    }
+    let saved: usize = lines.next()?.parse().ok()?;
+
+    let mut history = History::new();
+    for line in lines {
+        let mut tokens = line.split(' ');
+        let opt = |s: &str| match s {
+            "-" => Some(None),
+            s => s.parse().ok().map(Some),
+        };
+        let parent = opt(tokens.next()?)??;
+        let redo_child = opt(tokens.next()?)?;
+        let before = read_cursor(tokens.next()?)?;
+        let after = read_cursor(tokens.next()?)?;
+        let change = read_change(&mut tokens)?;
+
+        // parents come first, so there's no way to loop back
+        let idx = history.nodes.len();
+        if parent >= idx {
+            return None;
+        }
+        history.nodes.push(HistoryNode {
+            change,
+            before,
+            after,
+            parent: Some(parent),
+            children: Vec::new(),
+            redo_child,
+        });
+        history.nodes[parent].children.push(idx);
+    }
+
+    // the tree on disk matches the saved state, which is what we just opened
+    if saved >= history.nodes.len()
+        || history
+            .nodes
+            .iter()
+            .any(|n| n.redo_child.is_some_and(|c| !n.children.contains(&c)))
+    {
+        return None;
+    }
+
+    // and every change has to fit the file, so undoing and redoing can't run off the end
+    let mut root_len = len;
+    let mut idx = saved;
+    while let Some(parent) = history.nodes[idx].parent {
+        root_len = reverted_len(&history.nodes[idx].change, root_len)?;
+        idx = parent;
+    }
+    let mut lens = vec![root_len];
+    for node in &history.nodes[1..] {
+        lens.push(applied_len(&node.change, lens[node.parent?])?);
+    }
+    if lens[saved] != len {
+        return None;
+    }
+
+    history.current = saved;
+    Some((history, saved))
+}
+
+// the file length after the change, None if it doesn't fit in len bytes
+fn applied_len(change: &Change, len: usize) -> Option<usize> {
+    match change {
+        Change::Edit(idx, old, new) => {
+            let end = idx.checked_add(old.len())?;
+            // only an edit reaching the end can grow the file
+            (end <= len && (new.len() <= old.len() || end == len)).then(|| len.max(idx + new.len()))
+        }
+        Change::Insert(idx, new) => (*idx <= len).then(|| len + new.len()),
+        Change::Delete(idx, old) => (idx.checked_add(old.len())? <= len).then(|| len - old.len()),
+        Change::Group(changes) => changes
+            .iter()
+            .try_fold(len, |len, change| applied_len(change, len)),
+    }
+}
+
+// the file length before the change, going back from the length after it
+fn reverted_len(change: &Change, len: usize) -> Option<usize> {
+    match change {
+        Change::Edit(_, old, new) => len.checked_sub(new.len().saturating_sub(old.len())),
+        Change::Insert(_, new) => len.checked_sub(new.len()),
+        Change::Delete(_, old) => len.checked_add(old.len()),
+        Change::Group(changes) => changes
+            .iter()
+            .rev()
+            .try_fold(len, |len, change| reverted_len(change, len)),
+    }
+}
+
+fn write_cursor(cursor: CursorState) -> String {
+    format!(
+        "{},{},{},{}",
+        cursor.idx, cursor.is_selecting as u8, cursor.is_block as u8, cursor.selection_start
+    )
+}
+
+fn read_cursor(s: &str) -> Option<CursorState> {
+    let mut parts = s.split(',');
+    let mut next = || parts.next()?.parse::<usize>().ok();
+    Some(CursorState {
+        idx: next()?,
+        is_selecting: next()? == 1,
+        is_block: next()? == 1,
+        selection_start: next()?,
+    })
+}
+
+fn write_change(change: &Change, text: &mut String) {
+    let hex = |data: &[u8]| match data {
+        [] => "-".to_string(),
+        _ => data.iter().map(|b| format!("{b:02x}")).collect(),
+    };
+    match change {
+        Change::Edit(idx, old, new) => text.push_str(&format!("E {idx} {} {}", hex(old), hex(new))),
+        Change::Insert(idx, new) => text.push_str(&format!("I {idx} {}", hex(new))),
+        Change::Delete(idx, old) => text.push_str(&format!("D {idx} {}", hex(old))),
+        Change::Group(changes) => {
+            text.push_str(&format!("G {}", changes.len()));
+            for change in changes {
+                text.push(' ');
+                write_change(change, text);
+            }
+        }
+    }
+}
+
+fn read_change<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Option<Change> {
+    let kind = tokens.next()?;
+    let n: usize = tokens.next()?.parse().ok()?;
+    match kind {
+        "E" => Some(Change::Edit(n, read_hex(tokens)?, read_hex(tokens)?)),
+        "I" => Some(Change::Insert(n, read_hex(tokens)?)),
+        "D" => Some(Change::Delete(n, read_hex(tokens)?)),
+        "G" => (0..n)
+            .map(|_| read_change(tokens))
+            .collect::<Option<_>>()
+            .map(Change::Group),
+        _ => None,
+    }
+}
+
+fn read_hex<'a>(tokens: &mut impl Iterator<Item = &'a str>) -> Option<Vec<u8>> {
+    match tokens.next()? {
+        "-" => Some(Vec::new()),
+        s => decode_hex(s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let cursor = CursorState {
+            idx: 5,
+            is_selecting: true,
+            is_block: false,
+            selection_start: 2,
+        };
+        let mut history = History::new();
+        for change in [
+            Change::Edit(0, vec![0x00], vec![0xff]),
+            Change::Group(vec![
+                Change::Delete(4, vec![1, 2, 3]),
+                Change::Group(vec![
+                    Change::Insert(4, Vec::new()),
+                    Change::Group(Vec::new()),
+                ]),
+                Change::Edit(5, Vec::new(), vec![0xab]),
+            ]),
+            Change::Insert(0, vec![0xde, 0xad]),
+        ] {
+            let idx = history.nodes.len();
+            history.nodes.push(HistoryNode {
+                change,
+                before: cursor,
+                after: CursorState::default(),
+                parent: Some(idx - 1),
+                children: Vec::new(),
+                redo_child: None,
+            });
+            history.nodes[idx - 1].children.push(idx);
+            history.nodes[idx - 1].redo_child = Some(idx);
+        }
+
+        let text = write_history(&history, 2, "file.bin", 0x1234);
+        let (read, saved) = read_history(&text, "file.bin", 0x1234, 6).unwrap();
+        assert_eq!(saved, 2);
+        assert_eq!(read.current, 2);
+        // the root isn't written, redo falls back to its last child
+        assert_eq!(read.nodes[0].children, history.nodes[0].children);
+        assert_eq!(
+            format!("{:?}", &read.nodes[1..]),
+            format!("{:?}", &history.nodes[1..])
+        );
+        assert_eq!(write_history(&read, 2, "file.bin", 0x1234), text);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let text = write_history(&History::new(), 0, "file.bin", 0x1234);
+        assert!(read_history(&text, "other.bin", 0x1234, 0).is_none());
+        assert!(read_history(&text, "file.bin", 0x4321, 0).is_none());
+        assert!(read_history(&text, "file.bin", 0x1234, 0).is_some());
+    }
+
+    fn read(saved: usize, len: usize, nodes: &[&str]) -> Option<(History, usize)> {
+        let mut text = format!("hexhog history 1\nfile.bin\n{:016x}\n{saved}\n", 0x1234);
+        for node in nodes {
+            text.push_str(&format!("{node}\n"));
+        }
+        read_history(&text, "file.bin", 0x1234, len)
+    }
+
+    #[test]
+    fn rejects_broken_trees() {
+        let cursor = "0,0,0,0 0,0,0,0";
+        // its own parent
+        assert!(read(0, 0, &[&format!("1 - {cursor} I 0 ab")]).is_none());
+        // redo following a node that isn't a child
+        let nodes = [
+            format!("0 - {cursor} I 0 ab"),
+            format!("0 1 {cursor} I 0 cd"),
+        ];
+        let nodes: Vec<&str> = nodes.iter().map(String::as_str).collect();
+        assert!(read(0, 0, &nodes).is_none());
+        // same tree with redo pointing the right way
+        let nodes = [
+            format!("0 2 {cursor} I 0 ab"),
+            format!("1 - {cursor} I 0 cd"),
+        ];
+        let nodes: Vec<&str> = nodes.iter().map(String::as_str).collect();
+        assert!(read(0, 0, &nodes).is_some());
+    }
+
+    #[test]
+    fn rejects_changes_past_the_end() {
+        let cursor = "0,0,0,0 0,0,0,0";
+        assert!(read(0, 3, &[&format!("0 - {cursor} D 50 abcd")]).is_none());
+        assert!(read(0, 3, &[&format!("0 - {cursor} E 2 aabb ccdd")]).is_none());
+        assert!(read(1, 3, &[&format!("0 - {cursor} I 4 ab")]).is_none());
+        // growing the file from the middle
+        assert!(read(1, 5, &[&format!("0 - {cursor} E 0 aa bbcc")]).is_none());
+
+        assert!(read(0, 5, &[&format!("0 - {cursor} D 1 abcd")]).is_some());
+        assert!(read(1, 1, &[&format!("0 - {cursor} D 1 abcd")]).is_some());
+        assert!(read(1, 5, &[&format!("0 - {cursor} E 2 aa bbccdd")]).is_some());
+    }
+}
@@ -1,9 +1,10 @@
 use std::{fs::File, io::Write};
 
-use crate::app::App;
+use crate::app::{App, change::Change};
 
 impl App {
     pub fn quit(&mut self) {
+        self.save_history();
         self.running = false;
     }
     //starting_line
@@ -20,12 +21,21 @@ impl App {
 
     //cursor
     pub fn get_idx(&self) -> usize {
-        self.cursor_y * 16 + self.cursor_x
+        self.cursor_y * self.bytes_per_row + self.cursor_x
     }
 
     pub fn set_idx(&mut self, idx: usize) {
-        self.cursor_y = idx / 16;
-        self.cursor_x = idx % 16;
+        self.cursor_y = idx / self.bytes_per_row;
+        self.cursor_x = idx % self.bytes_per_row;
+    }
+
+    // the cursor stays on the same byte, and that byte stays on screen
+    pub fn set_bytes_per_row(&mut self, bytes_per_row: usize) {
+        let idx = self.get_idx();
+        self.starting_line = self.starting_line * self.bytes_per_row / bytes_per_row;
+        self.bytes_per_row = bytes_per_row.max(1);
+        self.set_idx(idx);
+        self.set_startingline();
     }
 
     pub fn move_up(&mut self) {
@@ -33,7 +43,7 @@ impl App {
     }
     pub fn move_down(&mut self) {
         self.cursor_y += 1;
-        if self.cursor_y * 16 > self.data.len() {
+        if self.cursor_y * self.bytes_per_row > self.data.len() {
             self.cursor_y -= 1;
         }
     }
@@ -42,16 +52,16 @@ impl App {
     }
     pub fn move_page_down(&mut self) {
         self.cursor_y += self.frame_height;
-        if self.cursor_y * 16 > self.data.len() {
+        if self.cursor_y * self.bytes_per_row > self.data.len() {
             self.cursor_y -= self.frame_height;
         }
     }
     pub fn move_right(&mut self) {
         self.cursor_x += 1;
-        if self.get_idx() >= self.data.len() + 1 {
+        if self.get_idx() > self.data.len() {
             self.cursor_x -= 1;
         }
-        if self.cursor_x >= 16 {
+        if self.cursor_x >= self.bytes_per_row {
             self.cursor_x = 0;
             self.cursor_y += 1;
         }
@@ -61,29 +71,119 @@ impl App {
             if self.cursor_y == 0 {
                 return;
             }
-            self.cursor_x = 15;
+            self.cursor_x = self.bytes_per_row - 1;
             self.cursor_y = self.cursor_y.saturating_sub(1);
         } else {
             self.cursor_x -= 1;
         }
     }
 
+    // in nibble mode left and right step through the two halves of each byte
+    pub fn move_nibble_right(&mut self) {
+        if !self.low_nibble && self.get_idx() < self.data.len() {
+            self.low_nibble = true;
+            return;
+        }
+        let idx = self.get_idx();
+        self.move_right();
+        if self.get_idx() != idx {
+            self.low_nibble = false;
+        }
+    }
+    pub fn move_nibble_left(&mut self) {
+        if self.low_nibble {
+            self.low_nibble = false;
+            return;
+        }
+        if self.get_idx() > 0 {
+            self.move_left();
+            self.low_nibble = true;
+        }
+    }
+
+    // overwrites just the half of the byte under the cursor
+    pub fn write_nibble(&mut self, c: char) {
+        let Some(value) = c.to_digit(16) else {
+            return;
+        };
+        let idx = self.get_idx();
+        let old: Vec<u8> = self.data.get(idx).copied().into_iter().collect();
+        let byte = old.first().copied().unwrap_or(0);
+        let new = match self.low_nibble {
+            true => (byte & 0xF0) | value as u8,
+            false => (byte & 0x0F) | (value as u8) << 4,
+        };
+        self.do_change(Change::Edit(idx, old, vec![new]));
+        self.move_nibble_right();
+    }
+
     //selection
     pub fn selection_range(&self) -> (usize, usize) {
         if !self.is_selecting {
             return (self.get_idx(), self.get_idx());
         }
+        if self.is_block {
+            let ranges = self.selection_ranges();
+            if let (Some(first), Some(last)) = (ranges.first(), ranges.last()) {
+                return (first.0, last.1);
+            }
+        }
         (
             self.get_idx().min(self.selection_start),
             self.get_idx()
                 .max(self.selection_start)
-                .min(self.data.len() - 1),
+                .min(self.data.len().saturating_sub(1)),
         )
     }
 
-    pub fn get_selection_data(&self) -> Vec<u8> {
+    // one range per row for block selections, just the one otherwise
+    pub fn selection_ranges(&self) -> Vec<(usize, usize)> {
+        if !(self.is_selecting && self.is_block) {
+            return vec![self.selection_range()];
+        }
+        let (start_y, start_x) = (
+            self.selection_start / self.bytes_per_row,
+            self.selection_start % self.bytes_per_row,
+        );
+        let (left, right) = (start_x.min(self.cursor_x), start_x.max(self.cursor_x));
+
+        (start_y.min(self.cursor_y)..=start_y.max(self.cursor_y))
+            .map(|row| {
+                (
+                    row * self.bytes_per_row + left,
+                    (row * self.bytes_per_row + right).min(self.data.len().saturating_sub(1)),
+                )
+            })
+            .take_while(|(x, y)| x <= y)
+            .collect()
+    }
+
+    pub fn is_selected(&self, pos: usize) -> bool {
+        if !self.is_selecting {
+            return false;
+        }
+        if self.is_block {
+            let (start_y, start_x) = (
+                self.selection_start / self.bytes_per_row,
+                self.selection_start % self.bytes_per_row,
+            );
+            let (row, col) = (pos / self.bytes_per_row, pos % self.bytes_per_row);
+            return pos < self.data.len()
+                && start_y.min(self.cursor_y) <= row
+                && row <= start_y.max(self.cursor_y)
+                && start_x.min(self.cursor_x) <= col
+                && col <= start_x.max(self.cursor_x);
+        }
         let (x, y) = self.selection_range();
-        self.data[x..(y + 1)].to_vec()
+        x <= pos && pos <= y
+    }
+
+    pub fn get_selection_data(&self) -> Vec<u8> {
+        self.selection_ranges()
+            .iter()
+            .flat_map(|(x, y)| self.data.get(*x..(y + 1)).unwrap_or_default())
+            .copied()
+            .collect()
     }
 
     //buffer
@@ -116,18 +216,13 @@ impl App {
     }
 
     pub fn insert_data(&mut self, idx: usize, new: Vec<u8>) {
-        for (i, b) in new.iter().enumerate() {
-            self.data.insert(idx + i, *b);
-        }
+        self.data.splice(idx..idx, new);
     }
 
     pub fn delete_data(&mut self, idx: usize, amt: usize) {
-        for _ in 0..amt {
-            if idx < self.data.len() {
-                self.data.remove(idx);
-            } else {
-                break;
-            }
+        let end = idx.saturating_add(amt).min(self.data.len());
+        if idx < end {
+            self.data.drain(idx..end);
         }
     }
 
@@ -136,5 +231,9 @@ impl App {
             .unwrap()
             .write_all(&self.data)
             .unwrap();
+        self.saved_data = self.data.clone();
+        self.end_group();
+        self.saved_node = self.history.current;
+        self.save_history();
     }
 }
@@ -1,6 +1,8 @@
-use std::{fs::read_to_string, str::FromStr};
+use std::{collections::HashMap, fs::read_to_string, path::PathBuf, str::FromStr};
 
 use crate::byte::{Byte, ByteType};
+use crate::keys::parse_keys;
+use crossterm::event::KeyEvent;
 use ratatui::style::{Color, Style};
 use toml::Table;
 
@@ -14,6 +16,7 @@ pub struct ColorScheme {
     pub primary: Color,
     pub border: Color,
     pub select: Color,
+    pub modified: Color,
     pub background: Color,
 }
 
@@ -49,9 +52,69 @@ impl Charset {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PasteMode {
+    Insert,
+    Overwrite,
+}
+
+// what modified bytes are compared against
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ModifiedMode {
+    Off,
+    Load,
+    Save,
+}
+
+impl ModifiedMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(ModifiedMode::Off),
+            "load" => Some(ModifiedMode::Load),
+            "save" => Some(ModifiedMode::Save),
+            _ => None,
+        }
+    }
+}
+
+// bytes per row, auto fits as many as the terminal is wide
+pub const MAX_BYTES_PER_ROW: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowWidth {
+    Fixed(usize),
+    Auto,
+}
+
+impl RowWidth {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auto" => Some(RowWidth::Auto),
+            _ => match s.parse() {
+                Ok(n) if (1..=MAX_BYTES_PER_ROW).contains(&n) => Some(RowWidth::Fixed(n)),
+                _ => None,
+            },
+        }
+    }
+}
+
+pub struct Editor {
+    pub paste_mode: PasteMode,
+    pub highlight_modified: ModifiedMode,
+    pub persistent_undo: bool,
+    pub kitty_keyboard: bool,
+    pub yank_history: usize,
+    pub bytes_per_row: RowWidth,
+    pub group: usize,
+    pub byte_separator: String,
+    pub group_separator: String,
+}
+
 pub struct Config {
     pub colorscheme: ColorScheme,
     pub charset: Charset,
+    pub editor: Editor,
+    pub macros: HashMap<char, Vec<KeyEvent>>,
 }
 
 impl Default for Config {
@@ -65,6 +128,7 @@ impl Default for Config {
                 non_ascii: Color::Green,
                 accent: Color::Blue,
                 select: Color::DarkGray,
+                modified: Color::Red,
                 border: Color::White,
                 primary: Color::White,
                 background: Color::Reset,
@@ -75,11 +139,27 @@ impl Default for Config {
                 ascii_other: '°',
                 non_ascii: '×',
             },
+            editor: Editor {
+                paste_mode: PasteMode::Insert,
+                highlight_modified: ModifiedMode::Save,
+                persistent_undo: true,
+                kitty_keyboard: false,
+                yank_history: 10,
+                bytes_per_row: RowWidth::Fixed(16),
+                group: 8,
+                byte_separator: " ".into(),
+                group_separator: "  ".into(),
+            },
+            macros: HashMap::new(),
         }
     }
 }
 
 impl Config {
+    pub fn path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("hexhog").join("config.toml"))
+    }
+
     pub fn toml_value_to_color(value: &toml::Value) -> Result<Color, String> {
         if let Some(s) = value.as_str() {
             Color::from_str(s).map_err(|_| "Invalid color name".into())
@@ -111,15 +191,9 @@ impl Config {
 
     fn set_color_field(table: &Table, field: &str, current: &mut Color) -> Result<(), String> {
         if let Some(value) = table.get(field) {
-            let color = Config::toml_value_to_color(value);
-            if color.is_err() {
-                return Err(format!(
-                    "Invalid color for field '{}' - {}",
-                    field,
-                    color.err().unwrap()
-                ));
-            } else {
-                *current = color.unwrap()
+            match Config::toml_value_to_color(value) {
+                Ok(color) => *current = color,
+                Err(e) => return Err(format!("Invalid color for field '{field}' - {e}")),
             }
         }
         Ok(())
@@ -145,6 +219,95 @@ impl Config {
         Ok(())
     }
 
+    fn set_paste_mode_field(
+        table: &Table,
+        field: &str,
+        current: &mut PasteMode,
+    ) -> Result<(), String> {
+        if let Some(value) = table.get(field) {
+            *current = match value.as_str() {
+                Some("insert") => PasteMode::Insert,
+                Some("overwrite") => PasteMode::Overwrite,
+                _ => {
+                    return Err(format!(
+                        "Field '{field}' must be either \"insert\" or \"overwrite\""
+                    ));
+                }
+            };
+        }
+        Ok(())
+    }
+
+    fn set_modified_mode_field(
+        table: &Table,
+        field: &str,
+        current: &mut ModifiedMode,
+    ) -> Result<(), String> {
+        if let Some(value) = table.get(field) {
+            match value.as_str().and_then(ModifiedMode::parse) {
+                Some(mode) => *current = mode,
+                None => {
+                    return Err(format!(
+                        "Field '{field}' must be \"off\", \"load\" or \"save\""
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_bool_field(table: &Table, field: &str, current: &mut bool) -> Result<(), String> {
+        if let Some(value) = table.get(field) {
+            match value.as_bool() {
+                Some(b) => *current = b,
+                None => return Err(format!("Field '{field}' must be true or false")),
+            }
+        }
+        Ok(())
+    }
+
+    fn set_row_width_field(
+        table: &Table,
+        field: &str,
+        current: &mut RowWidth,
+    ) -> Result<(), String> {
+        if let Some(value) = table.get(field) {
+            let width = match value.as_integer() {
+                Some(n) => RowWidth::parse(&n.to_string()),
+                None => value.as_str().and_then(RowWidth::parse),
+            };
+            match width {
+                Some(width) => *current = width,
+                None => {
+                    return Err(format!(
+                        "Field '{field}' must be a number from 1 to {MAX_BYTES_PER_ROW} or \"auto\""
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_usize_field(table: &Table, field: &str, current: &mut usize) -> Result<(), String> {
+        if let Some(value) = table.get(field) {
+            match value.as_integer().and_then(|n| usize::try_from(n).ok()) {
+                Some(n) => *current = n,
+                None => return Err(format!("Field '{field}' must be a positive number")),
+            }
+        }
+        Ok(())
+    }
+
+    fn set_string_field(table: &Table, field: &str, current: &mut String) -> Result<(), String> {
+        if let Some(value) = table.get(field) {
+            match value.as_str() {
+                Some(s) => *current = s.to_string(),
+                None => return Err(format!("Field '{field}' must be a string")),
+            }
+        }
+        Ok(())
+    }
+
     pub fn read_config(path: &str) -> Result<Self, String> {
         let mut config = Config::default();
 
@@ -156,40 +319,84 @@ impl Config {
 
         let values = config_file.unwrap().parse::<Table>().unwrap();
 
-        if let Some(colors) = values.get("theme") {
-            if let Some(table) = colors.as_table() {
-                Config::set_color_field(table, "null", &mut config.colorscheme.null)?;
-                Config::set_color_field(
-                    table,
-                    "ascii_printable",
-                    &mut config.colorscheme.ascii_printable,
-                )?;
-                Config::set_color_field(
-                    table,
-                    "ascii_whitespace",
-                    &mut config.colorscheme.ascii_whitespace,
-                )?;
-                Config::set_color_field(table, "ascii_other", &mut config.colorscheme.ascii_other)?;
-                Config::set_color_field(table, "non_ascii", &mut config.colorscheme.non_ascii)?;
-                Config::set_color_field(table, "accent", &mut config.colorscheme.accent)?;
-                Config::set_color_field(table, "select", &mut config.colorscheme.select)?;
-                Config::set_color_field(table, "primary", &mut config.colorscheme.primary)?;
-                Config::set_color_field(table, "border", &mut config.colorscheme.border)?;
-                Config::set_color_field(table, "background", &mut config.colorscheme.background)?;
+        if let Some(colors) = values.get("theme")
+            && let Some(table) = colors.as_table()
+        {
+            Config::set_color_field(table, "null", &mut config.colorscheme.null)?;
+            Config::set_color_field(
+                table,
+                "ascii_printable",
+                &mut config.colorscheme.ascii_printable,
+            )?;
+            Config::set_color_field(
+                table,
+                "ascii_whitespace",
+                &mut config.colorscheme.ascii_whitespace,
+            )?;
+            Config::set_color_field(table, "ascii_other", &mut config.colorscheme.ascii_other)?;
+            Config::set_color_field(table, "non_ascii", &mut config.colorscheme.non_ascii)?;
+            Config::set_color_field(table, "accent", &mut config.colorscheme.accent)?;
+            Config::set_color_field(table, "select", &mut config.colorscheme.select)?;
+            Config::set_color_field(table, "modified", &mut config.colorscheme.modified)?;
+            Config::set_color_field(table, "primary", &mut config.colorscheme.primary)?;
+            Config::set_color_field(table, "border", &mut config.colorscheme.border)?;
+            Config::set_color_field(table, "background", &mut config.colorscheme.background)?;
+        }
+
+        if let Some(charset) = values.get("charset")
+            && let Some(table) = charset.as_table()
+        {
+            Config::set_charset_field(table, "null", &mut config.charset.null)?;
+            Config::set_charset_field(
+                table,
+                "ascii_whitespace",
+                &mut config.charset.ascii_whitespace,
+            )?;
+            Config::set_charset_field(table, "ascii_other", &mut config.charset.ascii_other)?;
+            Config::set_charset_field(table, "non_ascii", &mut config.charset.non_ascii)?;
+            Config::set_charset_field(table, "non_ascii", &mut config.charset.non_ascii)?;
+        }
+
+        if let Some(editor) = values.get("editor")
+            && let Some(table) = editor.as_table()
+        {
+            Config::set_paste_mode_field(table, "paste_mode", &mut config.editor.paste_mode)?;
+            Config::set_modified_mode_field(
+                table,
+                "highlight_modified",
+                &mut config.editor.highlight_modified,
+            )?;
+            Config::set_bool_field(table, "persistent_undo", &mut config.editor.persistent_undo)?;
+            Config::set_bool_field(table, "kitty_keyboard", &mut config.editor.kitty_keyboard)?;
+            Config::set_usize_field(table, "yank_history", &mut config.editor.yank_history)?;
+            // the picker names them 0 to 9
+            if !(1..=10).contains(&config.editor.yank_history) {
+                return Err("Field 'yank_history' must be a number from 1 to 10".into());
+            }
+            Config::set_row_width_field(table, "bytes_per_row", &mut config.editor.bytes_per_row)?;
+            Config::set_usize_field(table, "group", &mut config.editor.group)?;
+            if config.editor.group == 0 {
+                return Err("Field 'group' must be a positive number".into());
             }
+            Config::set_string_field(table, "byte_separator", &mut config.editor.byte_separator)?;
+            Config::set_string_field(table, "group_separator", &mut config.editor.group_separator)?;
         }
 
-        if let Some(charset) = values.get("charset") {
-            if let Some(table) = charset.as_table() {
-                Config::set_charset_field(table, "null", &mut config.charset.null)?;
-                Config::set_charset_field(
-                    table,
-                    "ascii_whitespace",
-                    &mut config.charset.ascii_whitespace,
-                )?;
-                Config::set_charset_field(table, "ascii_other", &mut config.charset.ascii_other)?;
-                Config::set_charset_field(table, "non_ascii", &mut config.charset.non_ascii)?;
-                Config::set_charset_field(table, "non_ascii", &mut config.charset.non_ascii)?;
+        if let Some(macros) = values.get("macros")
+            && let Some(table) = macros.as_table()
+        {
+            for (name, value) in table {
+                let mut chars = name.chars();
+                let register = match (chars.next(), chars.next()) {
+                    (Some(c), None) if c.is_ascii_lowercase() => c,
+                    _ => return Err(format!("Macro '{name}' must be named a single letter")),
+                };
+                let keys = value
+                    .as_str()
+                    .ok_or(format!("Macro '{name}' must be a string"))
+                    .and_then(parse_keys)
+                    .map_err(|e| format!("Invalid macro '{name}' - {e}"))?;
+                config.macros.insert(register, keys);
             }
         }
 
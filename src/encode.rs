@@ -0,0 +1,223 @@
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn base64(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(BASE64_CHARS[(n >> (18 - i * 6)) as usize & 0x3F] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+    s
+}
+
+// fnv-1a, stable across versions unlike DefaultHasher
+pub fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// accepts things like "DE AD", "dead", "0xde, 0xad" or "\xde\xad"
+pub fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.replace("\\x", " ");
+    let mut data = Vec::new();
+
+    for token in text.split(|c: char| c.is_whitespace() || c == ',' || c == ':') {
+        let token = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+
+        if token.len() % 2 != 0 || !token.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+
+        for i in (0..token.len()).step_by(2) {
+            data.push(u8::from_str_radix(&token[i..i + 2], 16).ok()?);
+        }
+    }
+
+    if data.is_empty() { None } else { Some(data) }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Ascii,
+    Latin1,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl TextEncoding {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "ascii" => Some(TextEncoding::Ascii),
+            "latin1" => Some(TextEncoding::Latin1),
+            "str" | "utf8" => Some(TextEncoding::Utf8),
+            "utf16" | "utf16le" => Some(TextEncoding::Utf16Le),
+            "utf16be" => Some(TextEncoding::Utf16Be),
+            _ => None,
+        }
+    }
+
+    fn push(self, c: char, out: &mut Vec<u8>) -> Result<(), String> {
+        match self {
+            TextEncoding::Ascii if c.is_ascii() => out.push(c as u8),
+            TextEncoding::Latin1 if (c as u32) < 0x100 => out.push(c as u8),
+            TextEncoding::Ascii | TextEncoding::Latin1 => {
+                return Err(format!("'{c}' can't be encoded, use \\x"));
+            }
+            TextEncoding::Utf8 => out.extend(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            TextEncoding::Utf16Le => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    out.extend(unit.to_le_bytes());
+                }
+            }
+            TextEncoding::Utf16Be => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    out.extend(unit.to_be_bytes());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// text with \n, \t, \u{e9} and the like escaped, \xHH puts in a raw byte whatever the encoding
+pub fn encode_text(text: &str, encoding: TextEncoding) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            encoding.push(c, &mut out)?;
+            continue;
+        }
+        let c = match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&hex, 16) {
+                    Ok(b) if hex.len() == 2 => out.push(b),
+                    _ => return Err(format!("invalid escape '\\x{hex}'")),
+                }
+                continue;
+            }
+            Some('u') => {
+                let code: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                code.strip_prefix('{')
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or(format!("invalid escape '\\u{code}}}'"))?
+            }
+            Some(c @ ('\\' | '"' | '\'')) => c,
+            Some(c) => return Err(format!("invalid escape '\\{c}'")),
+            None => return Err("text ends with a lone \\".into()),
+        };
+        encoding.push(c, &mut out)?;
+    }
+    Ok(out)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    C,
+    Rust,
+    Python,
+    Go,
+    Java,
+    Hex,
+    Base64,
+    Escaped,
+}
+
+impl CopyFormat {
+    pub const ALL: [CopyFormat; 8] = [
+        CopyFormat::C,
+        CopyFormat::Rust,
+        CopyFormat::Python,
+        CopyFormat::Go,
+        CopyFormat::Java,
+        CopyFormat::Hex,
+        CopyFormat::Base64,
+        CopyFormat::Escaped,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CopyFormat::C => "c uint8_t[]",
+            CopyFormat::Rust => "rust [u8; N]",
+            CopyFormat::Python => "python b\"...\"",
+            CopyFormat::Go => "go []byte{}",
+            CopyFormat::Java => "java byte[]",
+            CopyFormat::Hex => "hex",
+            CopyFormat::Base64 => "base64",
+            CopyFormat::Escaped => "escaped string",
+        }
+    }
+
+    pub fn encode(self, data: &[u8]) -> String {
+        let list = |f: fn(u8) -> String| data.iter().map(|b| f(*b)).collect::<Vec<_>>().join(", ");
+
+        match self {
+            CopyFormat::C => format!(
+                "uint8_t data[{}] = {{ {} }};",
+                data.len(),
+                list(|b| format!("0x{b:02X}"))
+            ),
+            CopyFormat::Rust => format!(
+                "let data: [u8; {}] = [{}];",
+                data.len(),
+                list(|b| format!("0x{b:02X}"))
+            ),
+            CopyFormat::Python => {
+                let bytes: String = data.iter().map(|b| format!("\\x{b:02x}")).collect();
+                format!("b\"{bytes}\"")
+            }
+            CopyFormat::Go => format!("[]byte{{{}}}", list(|b| format!("0x{b:02X}"))),
+            CopyFormat::Java => format!(
+                "byte[] data = {{ {} }};",
+                list(|b| match b {
+                    0x80.. => format!("(byte) 0x{b:02X}"),
+                    _ => format!("0x{b:02X}"),
+                })
+            ),
+            CopyFormat::Hex => data
+                .iter()
+                .map(|b| format!("{b:02X}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            CopyFormat::Base64 => base64(data),
+            CopyFormat::Escaped => {
+                let mut s = String::from("\"");
+                for &b in data {
+                    match b {
+                        b'\n' => s.push_str("\\n"),
+                        b'\r' => s.push_str("\\r"),
+                        b'\t' => s.push_str("\\t"),
+                        b'\\' => s.push_str("\\\\"),
+                        b'"' => s.push_str("\\\""),
+                        b' '..=b'~' => s.push(b as char),
+                        _ => s.push_str(&format!("\\x{b:02x}")),
+                    }
+                }
+                s.push('"');
+                s
+            }
+        }
+    }
+}
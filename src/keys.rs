@@ -0,0 +1,116 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+// vim-like notation for storing key sequences, e.g. "0a<right><c-a>"
+
+const NAMED_KEYS: [(&str, KeyCode); 13] = [
+    ("right", KeyCode::Right),
+    ("left", KeyCode::Left),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("pgup", KeyCode::PageUp),
+    ("pgdn", KeyCode::PageDown),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("enter", KeyCode::Enter),
+    ("esc", KeyCode::Esc),
+    ("bs", KeyCode::Backspace),
+    ("del", KeyCode::Delete),
+    ("tab", KeyCode::Tab),
+];
+
+pub fn format_keys(keys: &[KeyEvent]) -> String {
+    let mut s = String::new();
+    for key in keys {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let name = match key.code {
+            KeyCode::Char('<') if !ctrl => "lt".to_string(),
+            KeyCode::Char(c) if !ctrl => {
+                s.push(c);
+                continue;
+            }
+            KeyCode::Char(c) => c.to_string(),
+            code => match NAMED_KEYS.iter().find(|(_, k)| *k == code) {
+                Some((name, _)) => name.to_string(),
+                None => continue,
+            },
+        };
+        match ctrl {
+            true => s.push_str(&format!("<c-{name}>")),
+            false => s.push_str(&format!("<{name}>")),
+        }
+    }
+    s
+}
+
+pub fn parse_keys(s: &str) -> Result<Vec<KeyEvent>, String> {
+    let mut keys = Vec::new();
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '<' {
+            keys.push(char_key(c));
+            continue;
+        }
+
+        let name: String = chars.by_ref().take_while(|c| *c != '>').collect();
+        let (modifiers, base) = match name.strip_prefix("c-") {
+            Some(base) => (KeyModifiers::CONTROL, base),
+            None => (KeyModifiers::NONE, name.as_str()),
+        };
+        let code = match NAMED_KEYS.iter().find(|(n, _)| *n == base) {
+            Some((_, code)) => *code,
+            None if base == "lt" => KeyCode::Char('<'),
+            None if base.chars().count() == 1 => KeyCode::Char(base.chars().next().unwrap()),
+            None => return Err(format!("unknown key '<{name}>'")),
+        };
+        keys.push(KeyEvent::new(code, modifiers));
+    }
+
+    Ok(keys)
+}
+
+fn char_key(c: char) -> KeyEvent {
+    let modifiers = match c.is_ascii_uppercase() {
+        true => KeyModifiers::SHIFT,
+        false => KeyModifiers::NONE,
+    };
+    KeyEvent::new(KeyCode::Char(c), modifiers)
+}
+
+// with the kitty protocol reporting all keys, shift+key comes in as the unshifted key, so it
+// gets shifted back here (assuming a us layout). keep_digits leaves shift+digit alone, since
+// telling shift+1 apart from ! is the whole point of enabling it when typing bytes
+pub fn normalize(mut key: KeyEvent, keep_digits: bool) -> KeyEvent {
+    if !key.modifiers.contains(KeyModifiers::SHIFT) {
+        return key;
+    }
+    if let KeyCode::Char(c) = key.code {
+        let shifted = match c {
+            'a'..='z' => c.to_ascii_uppercase(),
+            '`' => '~',
+            '-' => '_',
+            '=' => '+',
+            '[' => '{',
+            ']' => '}',
+            '\\' => '|',
+            ';' => ':',
+            '\'' => '"',
+            ',' => '<',
+            '.' => '>',
+            '/' => '?',
+            '1' if !keep_digits => '!',
+            '2' if !keep_digits => '@',
+            '3' if !keep_digits => '#',
+            '4' if !keep_digits => '$',
+            '5' if !keep_digits => '%',
+            '6' if !keep_digits => '^',
+            '7' if !keep_digits => '&',
+            '8' if !keep_digits => '*',
+            '9' if !keep_digits => '(',
+            '0' if !keep_digits => ')',
+            c => c,
+        };
+        key.code = KeyCode::Char(shifted);
+    }
+    key
+}
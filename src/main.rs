@@ -1,20 +1,22 @@
 mod app;
 mod byte;
 mod config;
+mod encode;
+mod keys;
+mod ops;
+mod terminal;
 
 use app::{App, Args};
 use clap::Parser;
 use color_eyre::Result;
 use config::Config;
+use crossterm::terminal::supports_keyboard_enhancement;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = Args::parse();
 
-    let config_file_path = dirs::config_dir()
-        .unwrap()
-        .join("hexhog")
-        .join("config.toml");
+    let config_file_path = Config::path().unwrap();
 
     let config = Config::read_config(config_file_path.to_str().unwrap()).unwrap_or_else(|e| {
         eprintln!("Error reading config: {e}");
@@ -22,9 +24,12 @@ fn main() -> Result<()> {
         Config::default()
     });
 
-    let app = App::new(args, config)?;
-    let terminal = ratatui::init();
+    let kitty_keyboard = config.editor.kitty_keyboard && supports_keyboard_enhancement()?;
+
+    let mut app = App::new(args, config)?;
+    app.kitty_keyboard = kitty_keyboard;
+    let terminal = terminal::init(kitty_keyboard)?;
     let result = app.run(terminal);
-    ratatui::restore();
+    terminal::restore(kitty_keyboard)?;
     result
 }
@@ -0,0 +1,192 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+// a width in bytes (1, 2, 4 or 8) and the byte order of the values
+#[derive(Clone, Copy)]
+pub struct Unit {
+    pub width: usize,
+    pub endian: Endian,
+}
+
+impl Unit {
+    pub fn to_bytes(self, value: u64) -> Vec<u8> {
+        let bytes = value.to_le_bytes();
+        let mut bytes = bytes[..self.width].to_vec();
+        if self.endian == Endian::Big {
+            bytes.reverse();
+        }
+        bytes
+    }
+
+    pub fn read(self, bytes: &[u8]) -> u64 {
+        let mut buf = [0; 8];
+        buf[..self.width].copy_from_slice(bytes);
+        if self.endian == Endian::Big {
+            buf[..self.width].reverse();
+        }
+        u64::from_le_bytes(buf)
+    }
+
+    pub fn bits(self) -> u32 {
+        self.width as u32 * 8
+    }
+
+    pub fn mask(self) -> u64 {
+        u64::MAX >> (64 - self.width * 8)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Transform {
+    Xor(u64),
+    And(u64),
+    Or(u64),
+    Not,
+    Add(u64),
+    Sub(u64),
+    Rol(u32),
+    Ror(u32),
+    Shl(u32),
+    Shr(u32),
+    SwapNibbles,
+    ReverseBits,
+}
+
+impl Transform {
+    fn apply(self, value: u64, unit: Unit) -> u64 {
+        let bits = unit.bits();
+        let value = match self {
+            Transform::Xor(v) => value ^ v,
+            Transform::And(v) => value & v,
+            Transform::Or(v) => value | v,
+            Transform::Not => !value,
+            Transform::Add(v) => value.wrapping_add(v),
+            Transform::Sub(v) => value.wrapping_sub(v),
+            Transform::Rol(n) => match n % bits {
+                0 => value,
+                n => value << n | value >> (bits - n),
+            },
+            Transform::Ror(n) => match n % bits {
+                0 => value,
+                n => value >> n | value << (bits - n),
+            },
+            Transform::Shl(n) => value.checked_shl(n).unwrap_or(0),
+            Transform::Shr(n) => value.checked_shr(n).unwrap_or(0),
+            Transform::SwapNibbles => {
+                (value & 0xF0F0F0F0F0F0F0F0) >> 4 | (value & 0x0F0F0F0F0F0F0F0F) << 4
+            }
+            Transform::ReverseBits => value.reverse_bits() >> (64 - bits),
+        };
+        value & unit.mask()
+    }
+}
+
+// applies the transform to every whole unit, a trailing partial unit is left alone
+pub fn transform(data: &[u8], op: Transform, unit: Unit) -> Vec<u8> {
+    let mut new = data.to_vec();
+    for chunk in new.chunks_exact_mut(unit.width) {
+        let value = op.apply(unit.read(chunk), unit);
+        chunk.copy_from_slice(&unit.to_bytes(value));
+    }
+    new
+}
+
+// bitwise op with a repeating key, byte by byte
+pub fn transform_key(data: &[u8], op: fn(u64) -> Transform, key: &[u8]) -> Vec<u8> {
+    let unit = Unit {
+        width: 1,
+        endian: Endian::Little,
+    };
+    data.iter()
+        .zip(key.iter().cycle())
+        .map(|(b, k)| op(*k as u64).apply(*b as u64, unit) as u8)
+        .collect()
+}
+
+// these leave a trailing partial unit/record alone too
+pub fn swap_bytes(data: &[u8], width: usize) -> Vec<u8> {
+    let mut new = data.to_vec();
+    for chunk in new.chunks_exact_mut(width) {
+        chunk.reverse();
+    }
+    new
+}
+
+pub fn reverse_records(data: &[u8], size: usize) -> Vec<u8> {
+    let split = data.len() - data.len() % size;
+    let mut new: Vec<u8> = data[..split]
+        .chunks_exact(size)
+        .rev()
+        .flatten()
+        .copied()
+        .collect();
+    new.extend(&data[split..]);
+    new
+}
+
+pub fn sort_records(data: &[u8], size: usize) -> Vec<u8> {
+    let split = data.len() - data.len() % size;
+    let mut records: Vec<&[u8]> = data[..split].chunks_exact(size).collect();
+    records.sort();
+    let mut new = records.concat();
+    new.extend(&data[split..]);
+    new
+}
+
+// moves the len bytes at from so they start at to
+pub fn move_block(data: &[u8], from: usize, to: usize, len: usize) -> Vec<u8> {
+    let mut new = data.to_vec();
+    let block: Vec<u8> = new.drain(from..from + len).collect();
+    new.splice(to..to, block);
+    new
+}
+
+pub fn fill_pattern(len: usize, pattern: &[u8]) -> Vec<u8> {
+    pattern.iter().copied().cycle().take(len).collect()
+}
+
+pub fn fill_sequence(len: usize, start: u64, step: i64, unit: Unit) -> Vec<u8> {
+    let mut data = Vec::with_capacity(len + unit.width);
+    let mut value = start;
+    while data.len() < len {
+        data.extend(unit.to_bytes(value & unit.mask()));
+        value = value.wrapping_add_signed(step);
+    }
+    data.truncate(len);
+    data
+}
+
+pub fn fill_random(len: usize, seed: u64) -> Vec<u8> {
+    let mut rng = SplitMix64(seed);
+    let mut data = Vec::with_capacity(len + 8);
+    while data.len() < len {
+        data.extend(rng.next().to_le_bytes());
+    }
+    data.truncate(len);
+    data
+}
+
+pub fn random_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+// small and good enough, the point is being reproducible from a seed
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+}
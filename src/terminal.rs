@@ -0,0 +1,37 @@
+use std::io::stdout;
+
+use color_eyre::Result;
+use crossterm::{
+    event::{
+        DisableBracketedPaste, EnableBracketedPaste, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute,
+};
+use ratatui::DefaultTerminal;
+
+// setting the terminal up and back, also used to hand it over to other programs
+
+pub fn init(kitty_keyboard: bool) -> Result<DefaultTerminal> {
+    let terminal = ratatui::init();
+    execute!(stdout(), EnableBracketedPaste)?;
+    if kitty_keyboard {
+        execute!(
+            stdout(),
+            PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+            )
+        )?;
+    }
+    Ok(terminal)
+}
+
+pub fn restore(kitty_keyboard: bool) -> Result<()> {
+    if kitty_keyboard {
+        execute!(stdout(), PopKeyboardEnhancementFlags)?;
+    }
+    execute!(stdout(), DisableBracketedPaste)?;
+    ratatui::restore();
+    Ok(())
+}